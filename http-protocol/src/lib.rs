@@ -34,10 +34,14 @@
 // Request-URI    = "*" | absoluteURI | abs_path | authority
 
 // HTTP-Version   = "HTTP" "/" 1*DIGIT "." 1*DIGIT
-#[macro_use]
+
 extern crate common_failures;
-#[macro_use]
 extern crate failure;
+extern crate idna;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(test)]
+extern crate serde_json;
 
 pub mod uri;
 
@@ -45,7 +49,8 @@ use common_failures::prelude::*;
 
 use std::io::BufRead;
 
-enum Method {
+#[derive(Debug, PartialEq)]
+pub enum Method {
     Options,
     Get,
     Head,
@@ -57,6 +62,22 @@ enum Method {
     Extension(String),
 }
 
+impl std::fmt::Display for Method {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str(match self {
+            Method::Options => "OPTIONS",
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Trace => "TRACE",
+            Method::Connect => "CONNECT",
+            Method::Extension(m) => m.as_str(),
+        })
+    }
+}
+
 impl From<String> for Method {
     fn from(string: String) -> Method {
         match string.as_str() {
@@ -73,7 +94,8 @@ impl From<String> for Method {
     }
 }
 
-fn method(r: &mut BufRead) -> Result<Option<Method>> {
+#[allow(dead_code)]
+fn method(r: &mut dyn BufRead) -> Result<Option<Method>> {
     match next_token(r)? {
         Some(token) => Ok(Some(Method::from(token))),
         None => Ok(None),
@@ -85,7 +107,7 @@ struct ReadStep {
     done: bool,
 }
 
-fn next_token(r: &mut BufRead) -> Result<Option<String>> {
+fn next_token(r: &mut dyn BufRead) -> Result<Option<String>> {
     let mut token: String = String::new();
     while {
         let step: ReadStep = {
@@ -98,11 +120,11 @@ fn next_token(r: &mut BufRead) -> Result<Option<String>> {
                     break;
                 } else {
                     token.push(*b as char);
-                    len = len + 1;
+                    len += 1;
                 }
             }
             ReadStep {
-                len: len,
+                len,
                 done: done || len == 0,
             }
         };
@@ -117,34 +139,14 @@ fn next_token(r: &mut BufRead) -> Result<Option<String>> {
 }
 
 fn is_separator(c: u8) -> bool {
-    match c {
-        b'(' => true,
-        b')' => true,
-        b'<' => true,
-        b'>' => true,
-        b'@' => true,
-        b',' => true,
-        b';' => true,
-        b':' => true,
-        b'\\' => true,
-        b'"' => true,
-        b'/' => true,
-        b'[' => true,
-        b']' => true,
-        b'?' => true,
-        b'=' => true,
-        b'{' => true,
-        b'}' => true,
-        b' ' => true,
-        b'\t' => true,
-        _ => false,
-    }
+    b"()<>@,;:\\\"/[]?={} \t".contains(&c)
 }
 
 fn is_ctl(c: u8) -> bool {
     c < 32 || c == 127
 }
 
+#[allow(dead_code)]
 fn is_sp(c: u8) -> bool {
     c == 32
 }
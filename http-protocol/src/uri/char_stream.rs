@@ -1,51 +1,104 @@
-use common_failures::Result;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
 use std::fmt::Write;
 use std::io::Read;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
 use uri::token_buffer::ByteStream;
+use uri::token_buffer::Token;
 use uri::token_buffer::TokenBuffer;
 use uri::token_buffer::TokenStream;
 
 pub struct CharStream<T: TokenStream<u8>> {
     byte_stream: T,
+    lenient: bool,
+    offset: usize,
+    pending: Vec<u8>,
 }
 
 impl<R: Read> From<R> for CharStream<ByteStream<R>> {
     fn from(read: R) -> CharStream<ByteStream<R>> {
         let byte_stream = ByteStream::from(read);
-        CharStream { byte_stream }
+        CharStream {
+            byte_stream,
+            lenient: false,
+            offset: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<R: Read> CharStream<ByteStream<R>> {
+    /// Creates a stream that, like browsers do, reads a "%" that does not start a
+    /// valid escape as `Char::Percent` instead of failing, so it is kept as it is.
+    pub fn lenient(read: R) -> CharStream<ByteStream<R>> {
+        CharStream {
+            lenient: true,
+            ..CharStream::from(read)
+        }
+    }
+}
+
+impl<T: TokenStream<u8>> CharStream<T> {
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        let b = match self.pending.pop() {
+            Some(b) => Some(b),
+            None => self.byte_stream.next()?,
+        };
+        if b.is_some() {
+            self.offset += 1;
+        }
+        Ok(b)
+    }
+
+    fn unread(&mut self, b: u8) {
+        self.pending.push(b);
+        self.offset -= 1;
     }
 }
 
 impl<T: TokenStream<u8>> TokenStream<Char> for CharStream<T> {
     fn next(&mut self) -> Result<Option<Char>> {
-        let b = match self.byte_stream.next()? {
+        let b = match self.next_byte()? {
             Some(b) => b,
             None => return Ok(None),
         };
-        let c: Char;
-        if b == b'%' {
-            let b2 = match self.byte_stream.next()? {
-                Some(b) => match is_hex(b) {
-                    true => b,
-                    false => return Err(format_err!("Invalid escape sequence.")),
-                },
-                None => return Err(format_err!("Unexpected end of escape sequence.")),
-            };
-            let b3 = match self.byte_stream.next()? {
-                Some(b) => match is_hex(b) {
-                    true => b,
-                    false => return Err(format_err!("Invalid escape sequence.")),
-                },
-                None => return Err(format_err!("Unexpected end of escape sequence.")),
-            };
-            c = Char::Escaped((b, b2, b3));
-        } else {
-            c = Char::Ascii(b);
-        }
-        Ok(Some(c))
+        if b != b'%' {
+            return Ok(Some(Char::Ascii(b)));
+        }
+
+        let mut hex: Vec<u8> = Vec::new();
+        while hex.len() < 2 {
+            match self.next_byte()? {
+                Some(h) if is_hex(h) => hex.push(h),
+                Some(h) => {
+                    if !self.lenient {
+                        return Err(UriError::unexpected(
+                            Production::Escape,
+                            self.offset - 1,
+                            Some(Char::Ascii(h)),
+                        ));
+                    }
+                    self.unread(h);
+                    break;
+                }
+                None => {
+                    if !self.lenient {
+                        return Err(UriError::unexpected(Production::Escape, self.offset, None));
+                    }
+                    break;
+                }
+            }
+        }
+        if hex.len() < 2 {
+            while let Some(h) = hex.pop() {
+                self.unread(h);
+            }
+            return Ok(Some(Char::Percent));
+        }
+        Ok(Some(Char::Escaped((b, hex[0], hex[1]))))
     }
 }
 
@@ -54,17 +107,40 @@ impl<R: Read> From<R> for TokenBuffer<Char, CharStream<ByteStream<R>>> {
         TokenBuffer {
             stream: CharStream::from(from),
             buffer: Vec::new(),
+            offset: 0,
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Char {
     Ascii(u8),
     Escaped((u8, u8, u8)),
+    /// A "%" that does not start an escape, which only lenient parsing reads. It is
+    /// accepted wherever an escape is and kept as it is, so the input round-trips.
+    Percent,
+}
+
+impl Token for Char {
+    fn width(&self) -> usize {
+        match self {
+            Char::Ascii(_) => 1,
+            Char::Escaped(_) => 3,
+            Char::Percent => 1,
+        }
+    }
 }
 
 impl Char {
+    /// Returns the byte the character starts with in the input, "%" for an escape.
+    pub fn first_byte(&self) -> u8 {
+        match self {
+            Char::Ascii(b) => *b,
+            Char::Escaped((b, _, _)) => *b,
+            Char::Percent => b'%',
+        }
+    }
+
     pub fn is(&self, byte: u8) -> bool {
         match self {
             Char::Ascii(b) => *b == byte,
@@ -75,80 +151,89 @@ impl Char {
     pub fn is_escaped(&self) -> bool {
         match self {
             Char::Ascii(_) => false,
-            Char::Escaped(_) => true,
+            Char::Escaped(_) | Char::Percent => true,
         }
     }
 
     pub fn is_pchar(&self) -> bool {
         match self {
-            Char::Escaped(_) => true,
-            Char::Ascii(b) => {
-                is_unreserved(*b) || match b {
-                    b':' => true,
-                    b'@' => true,
-                    b'&' => true,
-                    b'=' => true,
-                    b'+' => true,
-                    b'$' => true,
-                    b',' => true,
-                    _ => false,
-                }
-            }
+            Char::Escaped(_) | Char::Percent => true,
+            Char::Ascii(b) => is_unreserved(*b) || is_sub_delim(*b) || matches!(b, b':' | b'@'),
         }
     }
 
-    pub fn is_uric(&self) -> bool {
+    pub fn is_sub_delim(&self) -> bool {
         match self {
-            Char::Ascii(b) => is_reserved(*b) || is_unreserved(*b),
-            Char::Escaped(_) => true,
+            Char::Ascii(b) => is_sub_delim(*b),
+            Char::Escaped(_) | Char::Percent => false,
         }
     }
 
-    pub fn is_uric_no_slash(&self) -> bool {
+    pub fn is_digit(&self) -> bool {
         match self {
-            Char::Escaped(_) => true,
-            Char::Ascii(b) => {
-                is_unreserved(*b) || match b {
-                    b';' => true,
-                    b'?' => true,
-                    b':' => true,
-                    b'@' => true,
-                    b'&' => true,
-                    b'=' => true,
-                    b'+' => true,
-                    b'$' => true,
-                    b',' => true,
-                    _ => false,
-                }
-            }
+            Char::Ascii(b) => is_digit(*b),
+            Char::Escaped(_) | Char::Percent => false,
         }
     }
 
-    pub fn is_digit(&self) -> bool {
+    pub fn is_hexdig(&self) -> bool {
         match self {
-            Char::Ascii(b) => is_digit(*b),
-            Char::Escaped(_) => false,
+            Char::Ascii(b) => is_hex(*b),
+            Char::Escaped(_) | Char::Percent => false,
         }
     }
 
     pub fn is_alpha(&self) -> bool {
         match self {
             Char::Ascii(b) => is_alpha(*b),
-            Char::Escaped(_) => false,
+            Char::Escaped(_) | Char::Percent => false,
         }
     }
 
     pub fn is_alphanum(&self) -> bool {
         match self {
             Char::Ascii(b) => is_alphanum(*b),
-            Char::Escaped(_) => false,
+            Char::Escaped(_) | Char::Percent => false,
         }
     }
 
     pub fn is_unreserved(&self) -> bool {
         match self {
             Char::Ascii(b) => is_unreserved(*b),
-            Char::Escaped(_) => false,
+            Char::Escaped(_) | Char::Percent => false,
+        }
+    }
+
+    /// Returns the byte this character stands for, decoding an escape.
+    pub fn byte(&self) -> u8 {
+        match self {
+            Char::Ascii(b) => *b,
+            Char::Escaped((_, h, l)) => hex_value(*h) << 4 | hex_value(*l),
+            Char::Percent => b'%',
+        }
+    }
+
+    /// Decodes an escaped unreserved character and uppercases the hex digits of
+    /// any other escape (RFC 3986, section 6.2.2). A lone "%" becomes "%25".
+    pub fn normalize(&self) -> Char {
+        match self {
+            Char::Ascii(b) => Char::Ascii(*b),
+            Char::Percent => Char::Escaped((b'%', b'2', b'5')),
+            Char::Escaped((p, h, l)) => {
+                let b = self.byte();
+                match is_unreserved(b) {
+                    true => Char::Ascii(b),
+                    false => Char::Escaped((*p, h.to_ascii_uppercase(), l.to_ascii_uppercase())),
+                }
+            }
+        }
+    }
+
+    pub fn to_ascii_lowercase(self) -> Char {
+        match self {
+            Char::Ascii(b) => Char::Ascii(b.to_ascii_lowercase()),
+            Char::Escaped(e) => Char::Escaped(e),
+            Char::Percent => Char::Percent,
         }
     }
 }
@@ -162,52 +247,39 @@ impl Display for Char {
                 fmt.write_char(bytes.1 as char)?;
                 fmt.write_char(bytes.2 as char)?;
             }
+            Char::Percent => fmt.write_char('%')?,
         };
         Ok(())
     }
 }
 
-fn is_reserved(b: u8) -> bool {
-    match b {
-        b';' => true,
-        b'/' => true,
-        b'?' => true,
-        b':' => true,
-        b'@' => true,
-        b'&' => true,
-        b'=' => true,
-        b'+' => true,
-        b'$' => true,
-        b',' => true,
-        _ => false,
-    }
+fn is_sub_delim(b: u8) -> bool {
+    matches!(
+        b,
+        b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+    )
 }
 
 fn is_unreserved(b: u8) -> bool {
-    is_alphanum(b) || is_mark(b)
-}
-
-fn is_mark(b: u8) -> bool {
-    match b {
-        b'-' => true,
-        b'_' => true,
-        b'.' => true,
-        b'!' => true,
-        b'~' => true,
-        b'*' => true,
-        b'\'' => true,
-        b'(' => true,
-        b')' => true,
-        _ => false,
-    }
+    is_alphanum(b) || matches!(b, b'-' | b'.' | b'_' | b'~')
 }
 
+#[allow(dead_code)]
 fn is_escaped(bytes: (u8, u8, u8)) -> bool {
     bytes.0 == b'%' && is_hex(bytes.1) && is_hex(bytes.2)
 }
 
+fn hex_value(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'A'..=b'F' => b - b'A' + 10,
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => 0,
+    }
+}
+
 fn is_hex(b: u8) -> bool {
-    (b >= 65 && b <= 70) || (b >= 97 && b <= 102)
+    is_digit(b) || (65..=70).contains(&b) || (97..=102).contains(&b)
 }
 
 fn is_alphanum(b: u8) -> bool {
@@ -219,15 +291,15 @@ fn is_alpha(b: u8) -> bool {
 }
 
 fn is_low_alpha(b: u8) -> bool {
-    b >= 97 && b <= 122
+    (97..=122).contains(&b)
 }
 
 fn is_up_alpha(b: u8) -> bool {
-    b >= 65 && b <= 90
+    (65..=90).contains(&b)
 }
 
 fn is_digit(b: u8) -> bool {
-    b >= 48 && b <= 57
+    (48..=57).contains(&b)
 }
 
 #[cfg(test)]
@@ -249,6 +321,49 @@ mod tests {
         let c = cs.next();
         assert!(c.is_err());
 
+        let mut cs: CharStream<_> = "%20%2f%7E".as_bytes().into();
+        assert_eq!(Some(Char::Escaped((b'%', b'2', b'0'))), cs.next()?);
+        assert_eq!(Some(Char::Escaped((b'%', b'2', b'f'))), cs.next()?);
+        assert_eq!(Some(Char::Escaped((b'%', b'7', b'E'))), cs.next()?);
+        assert_eq!(None, cs.next()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_error_offset() -> Result<()> {
+        let mut cs: CharStream<_> = "ab%2Gc".as_bytes().into();
+        assert_eq!(Some(Char::Ascii(b'a')), cs.next()?);
+        assert_eq!(Some(Char::Ascii(b'b')), cs.next()?);
+        let e = cs.next().unwrap_err();
+        assert_eq!(
+            "Unexpected character 'G' in escape sequence at byte 4.",
+            e.to_string()
+        );
+        assert_eq!(Some(b'G'), e.byte());
+
+        let mut cs: CharStream<_> = "a%20%".as_bytes().into();
+        cs.next()?;
+        cs.next()?;
+        assert_eq!(
+            "Unexpected end of escape sequence at byte 5.",
+            cs.next().unwrap_err().to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient() -> Result<()> {
+        let percent = Char::Percent;
+        let mut cs = CharStream::lenient("%%41%zz%4".as_bytes());
+        assert_eq!(Some(percent), cs.next()?);
+        assert_eq!(Some(Char::Escaped((b'%', b'4', b'1'))), cs.next()?);
+        assert_eq!(Some(percent), cs.next()?);
+        assert_eq!(Some(Char::Ascii(b'z')), cs.next()?);
+        assert_eq!(Some(Char::Ascii(b'z')), cs.next()?);
+        assert_eq!(Some(percent), cs.next()?);
+        assert_eq!(Some(Char::Ascii(b'4')), cs.next()?);
+        assert_eq!(None, cs.next()?);
         Ok(())
     }
 }
@@ -0,0 +1,481 @@
+//https://tools.ietf.org/html/rfc2397
+
+use std::fmt::Write;
+use std::str::FromStr;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::percent_decode;
+use uri::percent_decode_str;
+use uri::token_buffer::Token;
+use uri::EncodeSet;
+use uri::Uri;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A "data:" URI, which carries its content inline.
+///
+/// ```text
+/// dataurl   = "data:" [ mediatype ] [ ";base64" ] "," data
+/// mediatype = [ type "/" subtype ] *( ";" parameter )
+/// ```
+///
+/// The media type defaults to "text/plain;charset=US-ASCII", and the data is
+/// decoded when the URI is parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataUri {
+    media_type: String,
+    params: Vec<(String, String)>,
+    base64: bool,
+    data: Vec<u8>,
+}
+
+impl DataUri {
+    /// Returns a base64-encoded data URI with the given media type, e.g. "image/png".
+    pub fn new(media_type: &str, data: Vec<u8>) -> DataUri {
+        DataUri {
+            media_type: media_type.to_ascii_lowercase(),
+            params: Vec::new(),
+            base64: true,
+            data,
+        }
+    }
+
+    /// Adds a media type parameter, e.g. "charset".
+    pub fn with_param(mut self, key: &str, value: &str) -> DataUri {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Chooses between base64 and percent-encoding of the data. Percent-encoding
+    /// is shorter for mostly printable text.
+    pub fn with_base64(mut self, base64: bool) -> DataUri {
+        self.base64 = base64;
+        self
+    }
+
+    /// Returns the view of a URI with the scheme "data". The data ends before the
+    /// fragment, so a "?" is part of it. Error offsets are the ones in the URI, also
+    /// for the base64 data.
+    pub fn from_uri(uri: &Uri) -> Result<DataUri> {
+        let (content, offset) = uri.opaque_part_for("data")?;
+        parse_content(&content)
+            .map_err(|e| e.shifted(offset).with_input(uri.to_string().as_bytes()))
+    }
+
+    /// Returns the lowercase "type/subtype" without parameters.
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    /// Returns the decoded parameters of the media type in order, without "base64".
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the value of the first parameter with the given key, which is
+    /// compared case-insensitively.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    pub fn is_base64(&self) -> bool {
+        self.base64
+    }
+
+    /// Returns the decoded data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn to_uri(&self) -> Result<Uri> {
+        Uri::parse(self.to_string().as_bytes())
+    }
+}
+
+impl std::fmt::Display for DataUri {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str("data:")?;
+        encode(fmt, self.media_type.as_bytes(), |b| b == b'/')?;
+        for (k, v) in &self.params {
+            fmt.write_char(';')?;
+            encode(fmt, k.as_bytes(), |_| false)?;
+            fmt.write_char('=')?;
+            encode(fmt, v.as_bytes(), |_| false)?;
+        }
+        match self.base64 {
+            true => {
+                fmt.write_str(";base64,")?;
+                fmt.write_str(base64_encode(&self.data).as_str())
+            }
+            false => {
+                fmt.write_char(',')?;
+                encode(fmt, &self.data, |b| b == b'/' || b == b'=')
+            }
+        }
+    }
+}
+
+impl FromStr for DataUri {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<DataUri> {
+        DataUri::from_uri(&Uri::parse(s.as_bytes())?)
+    }
+}
+
+// Parses what follows "data:". Error offsets are the ones in the content.
+fn parse_content(content: &str) -> Result<DataUri> {
+    let comma = match content.find(',') {
+        Some(i) => i,
+        None => {
+            return Err(UriError::unexpected(
+                Production::MediaType,
+                content.len(),
+                None,
+            ))
+        }
+    };
+    // The parts of the header between ";" with their offsets.
+    let mut header: Vec<(usize, &str)> = Vec::new();
+    let mut start = 0;
+    for part in content[..comma].split(';') {
+        header.push((start, part));
+        start += part.len() + 1;
+    }
+    let base64 = header.len() > 1 && header[header.len() - 1].1.eq_ignore_ascii_case("base64");
+    if base64 {
+        header.pop();
+    }
+
+    let mut params: Vec<(String, String)> = Vec::new();
+    for (start, p) in &header[1..] {
+        let (k, v) = match p.find('=') {
+            Some(i) => (&p[..i], &p[i + 1..]),
+            None => {
+                let end = start + p.len();
+                let c = Char::Ascii(content.as_bytes()[end]);
+                return Err(UriError::unexpected(Production::Parameter, end, Some(c)));
+            }
+        };
+        let v = percent_decode_str(v).map_err(|e| e.shifted(start + k.len() + 1))?;
+        let v = match v.len() > 1 && v.starts_with('"') && v.ends_with('"') {
+            true => v[1..v.len() - 1].to_string(),
+            false => v.into_owned(),
+        };
+        let k = percent_decode_str(k).map_err(|e| e.shifted(*start))?;
+        params.push((k.into_owned(), v));
+    }
+
+    let mut media_type = percent_decode_str(header[0].1)?.to_ascii_lowercase();
+    if !media_type.is_empty() && !is_media_type(&media_type) {
+        let c = content.as_bytes().first().map(|b| Char::Ascii(*b));
+        return Err(UriError::invalid(Production::MediaType, 0, c));
+    }
+    if media_type.is_empty() {
+        media_type = "text/plain".to_string();
+        if !params
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case("charset"))
+        {
+            params.insert(0, ("charset".to_string(), "US-ASCII".to_string()));
+        }
+    }
+
+    let encoded = &content.as_bytes()[comma + 1..];
+    let data = match base64 {
+        true => base64_decode(encoded),
+        false => percent_decode(encoded).map(|d| d.into_owned()),
+    };
+    let data = data.map_err(|e| e.shifted(comma + 1))?;
+    Ok(DataUri {
+        media_type,
+        params,
+        base64,
+        data,
+    })
+}
+
+// type "/" subtype, each of which is a token of RFC 2045
+fn is_media_type(media_type: &str) -> bool {
+    let is_token = |s: &str| {
+        !s.is_empty()
+            && s.bytes()
+                .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&b))
+    };
+    match media_type.find('/') {
+        Some(i) => is_token(&media_type[..i]) && is_token(&media_type[i + 1..]),
+        None => false,
+    }
+}
+
+// Percent-encodes everything but the bytes of a path segment other than ","
+// and the additionally allowed ones.
+fn encode<F>(fmt: &mut std::fmt::Formatter, bytes: &[u8], allow: F) -> std::fmt::Result
+where
+    F: Fn(u8) -> bool,
+{
+    for b in bytes {
+        match (EncodeSet::PathSegment.allows(*b) && *b != b',' && *b != b'=') || allow(*b) {
+            true => fmt.write_char(*b as char)?,
+            false => write!(fmt, "%{:02X}", b)?,
+        }
+    }
+    Ok(())
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+// Decodes percent-encoded base64 with or without padding, skipping whitespace. Error
+// offsets are the ones in the encoded data.
+fn base64_decode(encoded: &[u8]) -> Result<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut n: u32 = 0;
+    let mut bits = 0;
+    let mut padding = 0;
+    let mut i = 0;
+    while i < encoded.len() {
+        let (c, b) = match encoded[i] {
+            b'%' => {
+                let end = encoded.len().min(i + 3);
+                let b = percent_decode(&encoded[i..end]).map_err(|e| e.shifted(i))?[0];
+                (Char::Escaped((b'%', encoded[i + 1], encoded[i + 2])), b)
+            }
+            b => (Char::Ascii(b), b),
+        };
+        let at = i;
+        i += c.width();
+        let value = match b {
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            _ if padding > 0 => None,
+            _ => BASE64.iter().position(|c| *c == b),
+        };
+        match value {
+            Some(v) => {
+                n = n << 6 | v as u32;
+                bits += 6;
+                if bits >= 8 {
+                    bits -= 8;
+                    data.push((n >> bits) as u8);
+                }
+            }
+            None => return Err(UriError::unexpected(Production::Base64, at, Some(c))),
+        }
+    }
+    if bits >= 6 || (padding > 0 && padding != bits / 2) {
+        let c = encoded.first().map(|b| Char::Ascii(*b));
+        return Err(UriError::invalid(Production::Base64, 0, c));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::error::UriErrorKind;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let d: DataUri = "data:,A%20brief%20note".parse()?;
+        assert_eq!("text/plain", d.media_type());
+        assert_eq!(Some("US-ASCII"), d.charset());
+        assert!(!d.is_base64());
+        assert_eq!(b"A brief note", d.data());
+
+        let d: DataUri = "data:text/plain;charset=iso-8859-7,%be%e1%be".parse()?;
+        assert_eq!(Some("iso-8859-7"), d.charset());
+        assert_eq!(b"\xbe\xe1\xbe", d.data());
+
+        let d: DataUri = "DATA:Image/GIF;BASE64,R0lGODdhMAAwAPAAAAAAAP==".parse()?;
+        assert_eq!("image/gif", d.media_type());
+        assert_eq!(None, d.charset());
+        assert!(d.is_base64());
+        assert_eq!(
+            b"GIF87a0\x000\x00\xf0\x00\x00\x00\x00\x00".to_vec(),
+            d.into_data()
+        );
+
+        let d: DataUri = "data:;charset=utf-8;x=%22a%3Bb%22,%C3%A4?b#frag".parse()?;
+        assert_eq!("text/plain", d.media_type());
+        assert_eq!(
+            vec![("charset", "utf-8"), ("x", "a;b")],
+            d.params().collect::<Vec<_>>()
+        );
+        assert_eq!("ä?b".as_bytes(), d.data());
+
+        let d: DataUri = "data:application/octet-stream;base64,SGVsbG8".parse()?;
+        assert_eq!(b"Hello", d.data());
+        let d: DataUri = "data:;base64,".parse()?;
+        assert_eq!(b"", d.data());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = [
+            (
+                "http://a/b,c",
+                UriErrorKind::NotAllowed,
+                Production::Scheme,
+                0,
+            ),
+            ("data", UriErrorKind::NotAllowed, Production::Uri, 0),
+            (
+                "data://a/,b",
+                UriErrorKind::NotAllowed,
+                Production::Authority,
+                7,
+            ),
+            (
+                "data:/,x",
+                UriErrorKind::UnexpectedByte,
+                Production::Path,
+                5,
+            ),
+            (
+                "data:text/plain",
+                UriErrorKind::UnexpectedEnd,
+                Production::MediaType,
+                15,
+            ),
+            (
+                "data:foo,x",
+                UriErrorKind::Invalid,
+                Production::MediaType,
+                5,
+            ),
+            (
+                "data:text/,x",
+                UriErrorKind::Invalid,
+                Production::MediaType,
+                5,
+            ),
+            (
+                "data:a/b/c,x",
+                UriErrorKind::Invalid,
+                Production::MediaType,
+                5,
+            ),
+            (
+                "data:text/plain;charset,a",
+                UriErrorKind::UnexpectedByte,
+                Production::Parameter,
+                23,
+            ),
+            (
+                "data:;a=1;b;base64,",
+                UriErrorKind::UnexpectedByte,
+                Production::Parameter,
+                11,
+            ),
+            (
+                "data:;a=%G0,",
+                UriErrorKind::UnexpectedByte,
+                Production::Escape,
+                9,
+            ),
+            (
+                "data:;base64,SGVsbG8=x",
+                UriErrorKind::UnexpectedByte,
+                Production::Base64,
+                21,
+            ),
+            (
+                "data:;base64,SG%2Fs%2D",
+                UriErrorKind::UnexpectedByte,
+                Production::Base64,
+                19,
+            ),
+            (
+                "data:;base64,S",
+                UriErrorKind::Invalid,
+                Production::Base64,
+                13,
+            ),
+            (
+                "data:;base64,SGVsbG8===",
+                UriErrorKind::Invalid,
+                Production::Base64,
+                13,
+            ),
+            (
+                "data:;base64,SG-s",
+                UriErrorKind::UnexpectedByte,
+                Production::Base64,
+                15,
+            ),
+            (
+                "data:,%G0",
+                UriErrorKind::UnexpectedByte,
+                Production::Escape,
+                7,
+            ),
+        ];
+        for (input, kind, production, offset) in errors.iter() {
+            let e = input.parse::<DataUri>().unwrap_err();
+            assert_eq!(*kind, e.kind(), "{}", input);
+            assert_eq!(*production, e.production(), "{}", input);
+            assert_eq!(*offset, e.offset(), "{}", input);
+        }
+
+        assert_eq!(
+            "Unexpected character '-' in base64 data at byte 15.\ndata:;base64,SG-s\n               ^",
+            "data:;base64,SG-s".parse::<DataUri>().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_to_uri() -> Result<()> {
+        let d = DataUri::new("image/png", b"\x89PNG\r\n\x1a\n\x00".to_vec());
+        assert_eq!("data:image/png;base64,iVBORw0KGgoA", d.to_string());
+        assert_eq!(d, d.to_uri()?.to_string().parse()?);
+
+        let d = DataUri::new("text/plain", "a b,c;d=e/f?ä#".as_bytes().to_vec())
+            .with_param("charset", "utf-8")
+            .with_base64(false);
+        assert_eq!(
+            "data:text/plain;charset=utf-8,a%20b%2Cc%3Bd=e/f%3F%C3%A4%23",
+            d.to_string()
+        );
+        assert_eq!(d, DataUri::from_uri(&d.to_uri()?)?);
+
+        for len in 0..8 {
+            let data: Vec<u8> = (0..len).map(|i| 0xf0 + i as u8).collect();
+            let d = DataUri::new("application/octet-stream", data.clone());
+            assert_eq!(data, d.to_string().parse::<DataUri>()?.into_data());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+use uri::Scheme;
+use uri::Uri;
+
+// The well-known ports of the schemes, as registered with IANA.
+const KNOWN_DEFAULT_PORTS: &[(&str, u16)] = &[
+    ("ftp", 21),
+    ("ssh", 22),
+    ("telnet", 23),
+    ("gopher", 70),
+    ("http", 80),
+    ("ws", 80),
+    ("nntp", 119),
+    ("imap", 143),
+    ("ldap", 389),
+    ("https", 443),
+    ("wss", 443),
+    ("rtsp", 554),
+    ("ldaps", 636),
+];
+
+fn registry() -> &'static RwLock<HashMap<String, u16>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<String, u16>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let ports = KNOWN_DEFAULT_PORTS
+            .iter()
+            .map(|(s, p)| (s.to_string(), *p))
+            .collect();
+        RwLock::new(ports)
+    })
+}
+
+/// Returns the default port of a scheme, which is compared case-insensitively.
+pub fn default_port(scheme: &str) -> Option<u16> {
+    let ports = registry().read().unwrap_or_else(|e| e.into_inner());
+    ports.get(&scheme.to_ascii_lowercase()).cloned()
+}
+
+/// Registers or replaces the default port of a scheme for the whole process.
+pub fn register_default_port(scheme: &str, port: u16) {
+    let mut ports = registry().write().unwrap_or_else(|e| e.into_inner());
+    ports.insert(scheme.to_ascii_lowercase(), port);
+}
+
+impl Scheme {
+    pub fn default_port(&self) -> Option<u16> {
+        default_port(&self.to_string())
+    }
+}
+
+impl Uri {
+    /// Returns the port, or the default port of the scheme if there is none.
+    pub fn port_or_known_default(&self) -> Option<u16> {
+        self.port()
+            .or_else(|| self.scheme.as_ref().and_then(|s| s.default_port()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common_failures::prelude::*;
+
+    #[test]
+    fn test_port_or_known_default() -> Result<()> {
+        let u: Uri = "http://a/".parse()?;
+        assert_eq!(None, u.port());
+        assert_eq!(Some(80), u.port_or_known_default());
+
+        let u: Uri = "HTTPS://a:8443/".parse()?;
+        assert_eq!(Some(8443), u.port_or_known_default());
+
+        let u: Uri = "wss://a:/".parse()?;
+        assert_eq!(Some(443), u.port_or_known_default());
+
+        let u: Uri = "//a/".parse()?;
+        assert_eq!(None, u.port_or_known_default());
+
+        let u: Uri = "x-test-scheme://a/".parse()?;
+        assert_eq!(None, u.port_or_known_default());
+        register_default_port("X-Test-Scheme", 4711);
+        assert_eq!(Some(4711), u.port_or_known_default());
+        assert_eq!(Some(4711), default_port("x-test-scheme"));
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use uri::char_stream::Char;
+
+pub type Result<T> = std::result::Result<T, UriError>;
+
+/// The error of the URI parsers. It tells where parsing failed, on which byte and
+/// in which production of the grammar. Errors returned by the public parse functions
+/// also carry the input, so `Display` can point at the offending byte:
+///
+/// ```text
+/// Unexpected character ' ' in host at byte 10.
+/// http://exa mple.com
+///           ^
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UriError {
+    kind: UriErrorKind,
+    production: Production,
+    offset: usize,
+    byte: Option<u8>,
+    input: Option<Vec<u8>>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UriErrorKind {
+    /// The byte at the offset is not allowed in the production.
+    UnexpectedByte,
+    /// The input ended before the production was complete.
+    UnexpectedEnd,
+    /// The production starting at the offset is well-formed, but its value is not,
+    /// e.g. an IPv6 address with two "::".
+    Invalid,
+    /// The production is well-formed, but not allowed where it is, e.g. a port
+    /// without a host or the asterisk-form request target with GET.
+    NotAllowed,
+    /// The decoded production is not UTF-8. The offset is the one of the first
+    /// invalid byte of the decoded bytes.
+    Utf8,
+    /// Reading the input failed.
+    Io(std::io::ErrorKind),
+}
+
+/// The productions of RFC 3986 (and RFC 6874 and RFC 2396) that are named in errors,
+/// those of the URI schemes with views, like "data:" and "tel:", and those of URI
+/// templates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Production {
+    Uri,
+    Scheme,
+    Authority,
+    Userinfo,
+    Host,
+    IpLiteral,
+    Ipv4Address,
+    Ipv6Address,
+    ZoneId,
+    IpvFuture,
+    Port,
+    Path,
+    Query,
+    Fragment,
+    Escape,
+    OriginForm,
+    AbsoluteForm,
+    AuthorityForm,
+    AsteriskForm,
+    MediaType,
+    Parameter,
+    Base64,
+    Address,
+    HeaderField,
+    Nid,
+    Nss,
+    RComponent,
+    QComponent,
+    TelephoneNumber,
+    Template,
+    Expression,
+    VariableName,
+    Prefix,
+}
+
+impl Display for Production {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(match self {
+            Production::Uri => "URI",
+            Production::Scheme => "scheme",
+            Production::Authority => "authority",
+            Production::Userinfo => "userinfo",
+            Production::Host => "host",
+            Production::IpLiteral => "IP literal",
+            Production::Ipv4Address => "IPv4 address",
+            Production::Ipv6Address => "IPv6 address",
+            Production::ZoneId => "zone ID",
+            Production::IpvFuture => "IPvFuture",
+            Production::Port => "port",
+            Production::Path => "path",
+            Production::Query => "query",
+            Production::Fragment => "fragment",
+            Production::Escape => "escape sequence",
+            Production::OriginForm => "origin-form",
+            Production::AbsoluteForm => "absolute-form",
+            Production::AuthorityForm => "authority-form",
+            Production::AsteriskForm => "asterisk-form",
+            Production::MediaType => "media type",
+            Production::Parameter => "parameter",
+            Production::Base64 => "base64 data",
+            Production::Address => "address",
+            Production::HeaderField => "header field",
+            Production::Nid => "NID",
+            Production::Nss => "NSS",
+            Production::RComponent => "r-component",
+            Production::QComponent => "q-component",
+            Production::TelephoneNumber => "telephone number",
+            Production::Template => "URI template",
+            Production::Expression => "expression",
+            Production::VariableName => "variable name",
+            Production::Prefix => "prefix modifier",
+        })
+    }
+}
+
+impl UriError {
+    pub(crate) fn new(
+        kind: UriErrorKind,
+        production: Production,
+        offset: usize,
+        byte: Option<u8>,
+    ) -> UriError {
+        UriError {
+            kind,
+            production,
+            offset,
+            byte,
+            input: None,
+        }
+    }
+
+    /// Returns an error for the character at `offset`, or for the end of the input
+    /// if there is none.
+    pub(crate) fn unexpected(production: Production, offset: usize, c: Option<Char>) -> UriError {
+        match c {
+            Some(c) => UriError::new(
+                UriErrorKind::UnexpectedByte,
+                production,
+                offset,
+                Some(c.first_byte()),
+            ),
+            None => UriError::new(UriErrorKind::UnexpectedEnd, production, offset, None),
+        }
+    }
+
+    pub(crate) fn invalid(production: Production, offset: usize, c: Option<Char>) -> UriError {
+        UriError::new(
+            UriErrorKind::Invalid,
+            production,
+            offset,
+            c.map(|c| c.first_byte()),
+        )
+    }
+
+    /// Attaches the input the error refers to, so it is shown by `Display`.
+    pub(crate) fn with_input(mut self, input: &[u8]) -> UriError {
+        self.input = Some(input.to_vec());
+        self
+    }
+
+    /// Moves the error by `n` bytes, for a production that was parsed on its own but
+    /// starts at byte `n` of the input.
+    pub(crate) fn shifted(mut self, n: usize) -> UriError {
+        self.offset += n;
+        self
+    }
+
+    pub fn kind(&self) -> UriErrorKind {
+        self.kind
+    }
+
+    pub fn production(&self) -> Production {
+        self.production
+    }
+
+    /// Returns the offset of the offending byte, which is the length of the input for
+    /// an unexpected end.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn byte(&self) -> Option<u8> {
+        self.byte
+    }
+
+    pub fn input(&self) -> Option<&[u8]> {
+        self.input.as_deref()
+    }
+}
+
+impl Display for UriError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        let (p, o) = (self.production, self.offset);
+        match (self.kind, self.byte) {
+            (UriErrorKind::UnexpectedByte, Some(b)) => write!(
+                fmt,
+                "Unexpected character '{}' in {} at byte {}.",
+                escape(b),
+                p,
+                o
+            )?,
+            (UriErrorKind::Io(kind), _) => {
+                write!(fmt, "Failed to read {} at byte {}: {:?}.", p, o, kind)?
+            }
+            (UriErrorKind::Invalid, _) => write!(fmt, "Invalid {} at byte {}.", p, o)?,
+            (UriErrorKind::NotAllowed, _) => {
+                write!(fmt, "The {} at byte {} is not allowed.", p, o)?
+            }
+            (UriErrorKind::Utf8, _) => {
+                write!(fmt, "Invalid UTF-8 in decoded {} at byte {}.", p, o)?
+            }
+            _ => write!(fmt, "Unexpected end of {} at byte {}.", p, o)?,
+        }
+        if let Some(input) = &self.input {
+            let mut line = String::new();
+            let mut column = 0;
+            for (i, b) in input.iter().enumerate() {
+                let e = escape(*b);
+                if i < self.offset {
+                    column += e.len();
+                }
+                line.push_str(&e);
+            }
+            write!(fmt, "\n{}\n{:>2$}", line, "^", column + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UriError {}
+
+// Escapes bytes that are not printable ASCII, so the caret lines up with the input.
+fn escape(b: u8) -> String {
+    match b {
+        b'\'' | b'"' | b'\\' => (b as char).to_string(),
+        _ => std::ascii::escape_default(b).map(|e| e as char).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let e = UriError::unexpected(Production::Host, 10, Some(Char::Ascii(b' ')));
+        assert_eq!(
+            "Unexpected character ' ' in host at byte 10.",
+            e.to_string()
+        );
+        assert_eq!(
+            "Unexpected character ' ' in host at byte 10.\nhttp://exa mple.com\n          ^",
+            e.with_input(b"http://exa mple.com").to_string()
+        );
+
+        let e = UriError::unexpected(Production::Escape, 9, None).with_input(b"http://a%");
+        assert_eq!(
+            "Unexpected end of escape sequence at byte 9.\nhttp://a%\n         ^",
+            e.to_string()
+        );
+
+        let e = UriError::unexpected(Production::Path, 10, Some(Char::Ascii(0xe4)))
+            .with_input(b"http://a/\xc3\xa4");
+        assert_eq!(
+            "Unexpected character '\\xc3' in path at byte 9.\nhttp://a/\\xc3\\xa4\n         ^",
+            UriError::unexpected(Production::Path, 9, Some(Char::Ascii(0xc3)))
+                .with_input(b"http://a/\xc3\xa4")
+                .to_string()
+        );
+        assert_eq!(Some(0xe4), e.byte());
+        assert_eq!(10, e.offset());
+        assert_eq!(Production::Path, e.production());
+        assert_eq!(UriErrorKind::UnexpectedByte, e.kind());
+    }
+}
@@ -0,0 +1,265 @@
+//https://tools.ietf.org/html/rfc8089
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::path::PathBuf;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::Authority;
+use uri::EncodeSet;
+use uri::Host;
+use uri::Hostport;
+use uri::PathSegments;
+use uri::RegName;
+use uri::Scheme;
+use uri::Segment;
+use uri::Uri;
+
+impl Uri {
+    /// Returns the "file:///" URI of an absolute path. Every byte of the path that is
+    /// not allowed in a path segment is percent-encoded, including bytes that are not
+    /// UTF-8. The errors carry the path as input.
+    pub fn from_file_path<P: AsRef<Path>>(path: P) -> Result<Uri> {
+        let path = path.as_ref();
+        let bytes = path_bytes(path.as_os_str())?;
+        if !path.is_absolute() {
+            let c = bytes.first().map(|b| Char::Ascii(*b));
+            return Err(UriError::unexpected(Production::Path, 0, c).with_input(bytes));
+        }
+        let segments = bytes
+            .split(|b| *b == b'/')
+            .map(|s| Segment {
+                pchars: s.iter().map(|b| encode_byte(*b)).collect(),
+                params: None,
+            })
+            .collect();
+        Ok(Uri {
+            scheme: Some(Scheme(b"file".iter().map(|b| Char::Ascii(*b)).collect())),
+            authority: Some(Authority {
+                userinfo: None,
+                hostport: Hostport(Host::RegName(RegName(Vec::new())), None),
+            }),
+            path: PathSegments { segments },
+            query: None,
+            fragment: None,
+        })
+    }
+
+    /// Returns the path of a "file:" URI whose host is empty or "localhost", as in
+    /// "file:///etc/hosts", "file://localhost/etc/hosts" and "file:/etc/hosts". Query
+    /// and fragment are ignored. The errors carry the URI as input.
+    pub fn to_file_path(&self) -> Result<PathBuf> {
+        let input = self.to_string();
+        self.file_path(&input)
+            .map_err(|e| e.with_input(input.as_bytes()))
+    }
+
+    fn file_path(&self, input: &str) -> Result<PathBuf> {
+        let byte = |i: usize| input.as_bytes().get(i).cloned();
+        let not_allowed =
+            |production, i| UriError::new(UriErrorKind::NotAllowed, production, i, byte(i));
+        let mut offset = match &self.scheme {
+            Some(s) if s.to_string().eq_ignore_ascii_case("file") => s.to_string().len() + 1,
+            Some(_) => return Err(not_allowed(Production::Scheme, 0)),
+            None => return Err(not_allowed(Production::Uri, 0)),
+        };
+        if let Some(a) = &self.authority {
+            offset += 2;
+            if a.userinfo.is_some() {
+                return Err(not_allowed(Production::Userinfo, offset));
+            }
+            let host = a.hostport.0.to_string();
+            if !(host.is_empty() || host.eq_ignore_ascii_case("localhost")) {
+                return Err(not_allowed(Production::Host, offset));
+            }
+            if a.hostport.1.is_some() {
+                return Err(not_allowed(Production::Port, offset + host.len() + 1));
+            }
+            offset += host.len();
+        }
+        if self.path.is_empty() && self.authority.is_some() {
+            return Ok(PathBuf::from("/"));
+        }
+        if !self.path.is_absolute() {
+            let c = byte(offset).map(Char::Ascii);
+            return Err(UriError::unexpected(Production::Path, offset, c));
+        }
+
+        let mut bytes: Vec<u8> = Vec::new();
+        for (i, s) in self.path.segments.iter().enumerate() {
+            let decoded = s.decode_with_params();
+            if decoded.contains(&b'/') || decoded.contains(&0) {
+                // The escape that decodes to a byte not allowed in file names
+                let text = s.to_string();
+                let escape = (0..text.len())
+                    .find(|i| {
+                        ["%2F", "%2f", "%00"]
+                            .iter()
+                            .any(|e| text[*i..].starts_with(e))
+                    })
+                    .unwrap_or(0);
+                return Err(not_allowed(Production::Escape, offset + escape));
+            }
+            if i > 0 {
+                bytes.push(b'/');
+            }
+            bytes.extend(decoded);
+            offset += s.to_string().len() + 1;
+        }
+        path_buf(bytes)
+    }
+}
+
+fn encode_byte(b: u8) -> Char {
+    match EncodeSet::PathSegment.allows(b) {
+        true => Char::Ascii(b),
+        false => {
+            let hex = format!("{:02X}", b).into_bytes();
+            Char::Escaped((b'%', hex[0], hex[1]))
+        }
+    }
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &OsStr) -> Result<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(path.as_bytes())
+}
+
+#[cfg(unix)]
+fn path_buf(bytes: Vec<u8>) -> Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &OsStr) -> Result<&[u8]> {
+    match path.to_str() {
+        Some(s) => Ok(s.as_bytes()),
+        // The offset of the invalid byte is unknown, as the path has no bytes here.
+        None => Err(UriError::new(UriErrorKind::Utf8, Production::Path, 0, None)),
+    }
+}
+
+#[cfg(not(unix))]
+fn path_buf(bytes: Vec<u8>) -> Result<PathBuf> {
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(PathBuf::from(s)),
+        Err(e) => {
+            let offset = e.utf8_error().valid_up_to();
+            Err(UriError::new(
+                UriErrorKind::Utf8,
+                Production::Path,
+                offset,
+                None,
+            ))
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use std::os::unix::ffi::OsStrExt;
+
+    #[test]
+    fn test_roundtrip() -> Result<()> {
+        let paths: [(&[u8], &str); 11] = [
+            (b"/", "file:///"),
+            (b"/etc/hosts", "file:///etc/hosts"),
+            (b"/tmp/dir/", "file:///tmp/dir/"),
+            (b"/tmp/a b", "file:///tmp/a%20b"),
+            (b"/tmp/100%", "file:///tmp/100%25"),
+            (b"/tmp/#hash", "file:///tmp/%23hash"),
+            (b"/tmp/what?", "file:///tmp/what%3F"),
+            (b"/tmp/a;b=c", "file:///tmp/a%3Bb=c"),
+            (b"/tmp/\xc3\xa4\xe2\x82\xac", "file:///tmp/%C3%A4%E2%82%AC"),
+            (b"/tmp/\xff\xfe", "file:///tmp/%FF%FE"),
+            (b"/a//b/./../c:d", "file:///a//b/./../c:d"),
+        ];
+        for (bytes, expected) in paths.iter() {
+            let path = Path::new(OsStr::from_bytes(bytes));
+            let u = Uri::from_file_path(path)?;
+            assert_eq!(*expected, u.to_string());
+            assert_eq!(u, Uri::parse(expected.as_bytes())?);
+            assert_eq!(*bytes, u.to_file_path()?.as_os_str().as_bytes());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_file_path() -> Result<()> {
+        let path = |s: &str| -> Result<PathBuf> { Uri::parse(s.as_bytes())?.to_file_path() };
+        assert_eq!(
+            PathBuf::from("/etc/hosts"),
+            path("file://localhost/etc/hosts")?
+        );
+        assert_eq!(
+            PathBuf::from("/etc/hosts"),
+            path("FILE://LocalHost/etc/hosts")?
+        );
+        assert_eq!(PathBuf::from("/etc/hosts"), path("file:/etc/hosts")?);
+        assert_eq!(PathBuf::from("/etc/hosts"), path("file:///etc/hosts?q#f")?);
+        assert_eq!(PathBuf::from("/a;b"), path("file:///a;b")?);
+        assert_eq!(PathBuf::from("/"), path("file://localhost")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_file_path_errors() {
+        use uri::error::UriErrorKind::*;
+
+        let error = |s: &str| {
+            let e = Uri::parse(s.as_bytes())
+                .unwrap()
+                .to_file_path()
+                .unwrap_err();
+            (e.kind(), e.production(), e.offset())
+        };
+        assert_eq!((NotAllowed, Production::Scheme, 0), error("http://a/b"));
+        assert_eq!((NotAllowed, Production::Uri, 0), error("/a/b"));
+        assert_eq!(
+            (NotAllowed, Production::Host, 7),
+            error("file://server/share/a")
+        );
+        assert_eq!(
+            (NotAllowed, Production::Userinfo, 7),
+            error("file://u@localhost/a")
+        );
+        assert_eq!(
+            (NotAllowed, Production::Port, 17),
+            error("file://localhost:80/a")
+        );
+        assert_eq!((UnexpectedByte, Production::Path, 5), error("file:a/b"));
+        assert_eq!((NotAllowed, Production::Escape, 9), error("file:///a%2Fb"));
+        assert_eq!(
+            (NotAllowed, Production::Escape, 11),
+            error("file:/a/b/c%00")
+        );
+        assert_eq!(
+            "The escape sequence at byte 9 is not allowed.\nfile:///a%2fb\n         ^",
+            Uri::parse(b"file:///a%2fb")
+                .unwrap()
+                .to_file_path()
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let e = Uri::from_file_path("tmp/a").unwrap_err();
+        assert_eq!(UriErrorKind::UnexpectedByte, e.kind());
+        assert_eq!(Production::Path, e.production());
+        assert_eq!(0, e.offset());
+        assert_eq!(Some(&b"tmp/a"[..]), e.input());
+
+        let e = Uri::from_file_path("").unwrap_err();
+        assert_eq!(UriErrorKind::UnexpectedEnd, e.kind());
+    }
+}
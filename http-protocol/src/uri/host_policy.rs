@@ -0,0 +1,683 @@
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use uri::percent_decode;
+use uri::whatwg::ends_in_a_number;
+use uri::whatwg::parse_ipv4;
+use uri::Host;
+use uri::IPLiteral;
+use uri::IPv4address;
+use uri::IPv6address;
+use uri::Uri;
+
+// Cloud instance metadata services: AWS, GCP and Azure on 169.254.169.254, AWS on
+// fd00:ec2::254 and Alibaba Cloud on 100.100.100.200.
+const METADATA_ADDRS: [IpAddr; 3] = [
+    IpAddr::V4(Ipv4Addr::new(169, 254, 169, 254)),
+    IpAddr::V4(Ipv4Addr::new(100, 100, 100, 200)),
+    IpAddr::V6(Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254)),
+];
+
+const METADATA_NAMES: [&str; 1] = ["metadata.google.internal"];
+
+/// The kind of network an address belongs to, from the point of view of a server
+/// that fetches user-supplied URIs. IPv4 addresses embedded in IPv4-mapped
+/// ("::ffff:0:0/96"), IPv4-compatible ("::/96"), IPv4-translated
+/// ("::ffff:0:0:0/96"), NAT64 ("64:ff9b::/96") and 6to4 ("2002::/16") IPv6
+/// addresses are classified as IPv4.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressClass {
+    /// "0.0.0.0/8" and "::".
+    Unspecified,
+    /// "127.0.0.0/8" and "::1".
+    Loopback,
+    /// The RFC 1918 ranges "10.0.0.0/8", "172.16.0.0/12" and "192.168.0.0/16",
+    /// unique local addresses "fc00::/7" and the deprecated site-local addresses
+    /// "fec0::/10".
+    Private,
+    /// The carrier-grade NAT range "100.64.0.0/10" of RFC 6598.
+    SharedAddressSpace,
+    /// "169.254.0.0/16" and "fe80::/10".
+    LinkLocal,
+    /// A cloud instance metadata service, like "169.254.169.254".
+    Metadata,
+    /// "224.0.0.0/4" and "ff00::/8".
+    Multicast,
+    /// "255.255.255.255".
+    Broadcast,
+    /// "240.0.0.0/4".
+    Reserved,
+    /// Any other address.
+    Global,
+}
+
+impl AddressClass {
+    pub fn of(ip: IpAddr) -> AddressClass {
+        let ip = unmap(ip);
+        if METADATA_ADDRS.contains(&ip) {
+            return AddressClass::Metadata;
+        }
+        match ip {
+            IpAddr::V4(ip) => {
+                let o = ip.octets();
+                match o {
+                    [0, ..] => AddressClass::Unspecified,
+                    [127, ..] => AddressClass::Loopback,
+                    [10, ..] => AddressClass::Private,
+                    [172, b, ..] if b & 0xf0 == 16 => AddressClass::Private,
+                    [192, 168, ..] => AddressClass::Private,
+                    [100, b, ..] if b & 0xc0 == 64 => AddressClass::SharedAddressSpace,
+                    [169, 254, ..] => AddressClass::LinkLocal,
+                    [255, 255, 255, 255] => AddressClass::Broadcast,
+                    [a, ..] if a & 0xf0 == 224 => AddressClass::Multicast,
+                    [a, ..] if a & 0xf0 == 240 => AddressClass::Reserved,
+                    _ => AddressClass::Global,
+                }
+            }
+            IpAddr::V6(ip) => {
+                let s = ip.segments();
+                match s {
+                    [0, 0, 0, 0, 0, 0, 0, 0] => AddressClass::Unspecified,
+                    [0, 0, 0, 0, 0, 0, 0, 1] => AddressClass::Loopback,
+                    [a, ..] if a & 0xfe00 == 0xfc00 => AddressClass::Private,
+                    [a, ..] if a & 0xffc0 == 0xfec0 => AddressClass::Private,
+                    [a, ..] if a & 0xffc0 == 0xfe80 => AddressClass::LinkLocal,
+                    [a, ..] if a & 0xff00 == 0xff00 => AddressClass::Multicast,
+                    _ => AddressClass::Global,
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AddressClass {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str(match self {
+            AddressClass::Unspecified => "unspecified",
+            AddressClass::Loopback => "loopback",
+            AddressClass::Private => "private",
+            AddressClass::SharedAddressSpace => "shared (CGNAT)",
+            AddressClass::LinkLocal => "link-local",
+            AddressClass::Metadata => "cloud metadata",
+            AddressClass::Multicast => "multicast",
+            AddressClass::Broadcast => "broadcast",
+            AddressClass::Reserved => "reserved",
+            AddressClass::Global => "global",
+        })
+    }
+}
+
+// Returns the IPv4 address embedded in an IPv4-mapped, IPv4-compatible,
+// IPv4-translated, NAT64 or 6to4 address. "::" and "::1" are no IPv4-compatible
+// addresses.
+fn unmap(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0, 0, 0] | [0, 0, 0, 0, 0, 0, 0, 1] => ip,
+            [0, 0, 0, 0, 0, 0xffff, hi, lo]
+            | [0, 0, 0, 0, 0, 0, hi, lo]
+            | [0, 0, 0, 0, 0xffff, 0, hi, lo]
+            | [0x64, 0xff9b, 0, 0, 0, 0, hi, lo]
+            | [0x2002, hi, lo, ..] => IpAddr::V4(Ipv4Addr::from((hi as u32) << 16 | lo as u32)),
+            _ => ip,
+        },
+        IpAddr::V4(_) => ip,
+    }
+}
+
+impl IPv4address {
+    pub fn class(&self) -> AddressClass {
+        AddressClass::of(IpAddr::V4(self.addr()))
+    }
+}
+
+impl IPv6address {
+    pub fn class(&self) -> AddressClass {
+        AddressClass::of(IpAddr::V6(self.addr()))
+    }
+}
+
+impl Host {
+    /// Returns the address the host stands for without a DNS lookup. Unlike
+    /// `ip_addr`, this includes the numeric forms that resolvers and browsers
+    /// accept for IPv4, like "2130706433", "0x7f.1" or "0177.0.0.1".
+    pub fn effective_ip_addr(&self) -> Option<IpAddr> {
+        match self {
+            Host::IPLiteral(IPLiteral::IPv6address(ip)) => Some(IpAddr::V6(ip.addr())),
+            Host::IPLiteral(IPLiteral::IPvFuture(_)) => None,
+            Host::IPv4address(ip) => Some(IpAddr::V4(ip.addr())),
+            Host::Hostname(_) | Host::RegName(_) => {
+                let name = ascii_name(self);
+                match !name.is_empty() && ends_in_a_number(&name) {
+                    true => parse_ipv4(&name).map(|ip| IpAddr::V4(Ipv4Addr::from(ip))),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    /// Returns the class of the address the host stands for without a DNS lookup,
+    /// with "localhost" and its subdomains as loopback, as reserved by RFC 6761.
+    pub fn address_class(&self) -> Option<AddressClass> {
+        if let Some(ip) = self.effective_ip_addr() {
+            return Some(AddressClass::of(ip));
+        }
+        match is_localhost(&trim_dot(&ascii_name(self))) {
+            true => Some(AddressClass::Loopback),
+            false => None,
+        }
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.address_class() == Some(AddressClass::Loopback)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.address_class() == Some(AddressClass::Private)
+    }
+
+    pub fn is_shared_address_space(&self) -> bool {
+        self.address_class() == Some(AddressClass::SharedAddressSpace)
+    }
+
+    /// Returns whether the host is link-local, including the metadata services in
+    /// the link-local ranges.
+    pub fn is_link_local(&self) -> bool {
+        match self.effective_ip_addr().map(unmap) {
+            Some(IpAddr::V4(ip)) => ip.octets()[..2] == [169, 254],
+            Some(IpAddr::V6(ip)) => ip.segments()[0] & 0xffc0 == 0xfe80,
+            None => false,
+        }
+    }
+
+    pub fn is_metadata(&self) -> bool {
+        self.address_class() == Some(AddressClass::Metadata)
+            || METADATA_NAMES.contains(&trim_dot(&ascii_name(self)).as_str())
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.address_class() == Some(AddressClass::Multicast)
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        self.address_class() == Some(AddressClass::Unspecified)
+    }
+}
+
+// Returns the lowercase ASCII form of a host name, percent-decoded and mapped with
+// UTS #46 the way resolvers see it.
+fn ascii_name(host: &Host) -> String {
+    let s = host.to_string();
+    let decoded = match percent_decode(s.as_bytes()) {
+        Ok(d) => String::from_utf8_lossy(&d).into_owned(),
+        Err(_) => s,
+    };
+    match idna::domain_to_ascii(&decoded) {
+        Ok(ascii) => ascii,
+        Err(_) => decoded.to_ascii_lowercase(),
+    }
+}
+
+fn trim_dot(name: &str) -> String {
+    name.strip_suffix('.').unwrap_or(name).to_string()
+}
+
+fn is_localhost(name: &str) -> bool {
+    name == "localhost" || name.ends_with(".localhost")
+}
+
+// Returns whether the name is the domain or one of its subdomains.
+fn in_domain(name: &str, domain: &str) -> bool {
+    name == domain || (name.ends_with(domain) && name[..name.len() - domain.len()].ends_with('.'))
+}
+
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (unmap(ip), unmap(network)) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX
+                .checked_shl(32 - prefix.min(32) as u32)
+                .unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX
+                .checked_shl(128 - prefix.min(128) as u32)
+                .unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Why a `HostPolicy` denied a URI or address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DenyReason {
+    /// The URI has no host, or an empty one.
+    NoHost,
+    /// The host name is denied, like "metadata.google.internal".
+    Name(String),
+    /// The host name stands for an address of a denied class, like "localhost".
+    NameClass(String, AddressClass),
+    /// The address is of a denied class.
+    Address(IpAddr, AddressClass),
+    /// The address is in a denied network.
+    Network(IpAddr),
+}
+
+impl std::fmt::Display for DenyReason {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DenyReason::NoHost => write!(fmt, "The URI has no host."),
+            DenyReason::Name(name) => write!(fmt, "The host '{}' is denied.", name),
+            DenyReason::NameClass(name, class) => {
+                write!(fmt, "The host '{}' is a {} host.", name, class)
+            }
+            DenyReason::Address(ip, class) => write!(fmt, "{} is a {} address.", ip, class),
+            DenyReason::Network(ip) => write!(fmt, "{} is in a denied network.", ip),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostVerdict {
+    Allow,
+    Deny(DenyReason),
+}
+
+impl HostVerdict {
+    pub fn is_allowed(&self) -> bool {
+        *self == HostVerdict::Allow
+    }
+}
+
+/// Decides whether a server may fetch a user-supplied URI, to guard against
+/// server-side request forgery. The default policy denies every class of address
+/// except `AddressClass::Global`, "localhost" and the metadata service names:
+///
+/// ```
+/// use http_protocol::uri::HostPolicy;
+/// use http_protocol::uri::Uri;
+///
+/// let policy = HostPolicy::new();
+/// let uri = Uri::parse(b"http://0x7f.1:8080/admin").unwrap();
+/// assert!(!policy.check(&uri).is_allowed());
+/// ```
+///
+/// Hostnames are not resolved, so a name that resolves to a denied address is
+/// allowed by `check`; check each resolved address with `check_addr` before
+/// connecting.
+#[derive(Clone, Debug)]
+pub struct HostPolicy {
+    denied_classes: Vec<AddressClass>,
+    allowed_names: Vec<String>,
+    denied_names: Vec<String>,
+    allowed_networks: Vec<(IpAddr, u8)>,
+    denied_networks: Vec<(IpAddr, u8)>,
+}
+
+impl HostPolicy {
+    pub fn new() -> HostPolicy {
+        HostPolicy {
+            denied_classes: vec![
+                AddressClass::Unspecified,
+                AddressClass::Loopback,
+                AddressClass::Private,
+                AddressClass::SharedAddressSpace,
+                AddressClass::LinkLocal,
+                AddressClass::Metadata,
+                AddressClass::Multicast,
+                AddressClass::Broadcast,
+                AddressClass::Reserved,
+            ],
+            allowed_names: Vec::new(),
+            denied_names: METADATA_NAMES.iter().map(|n| n.to_string()).collect(),
+            allowed_networks: Vec::new(),
+            denied_networks: Vec::new(),
+        }
+    }
+
+    pub fn allow_class(mut self, class: AddressClass) -> HostPolicy {
+        self.denied_classes.retain(|c| *c != class);
+        self
+    }
+
+    pub fn deny_class(mut self, class: AddressClass) -> HostPolicy {
+        if !self.denied_classes.contains(&class) {
+            self.denied_classes.push(class);
+        }
+        self
+    }
+
+    /// Allows the domain and its subdomains, whatever they stand for. Allowed
+    /// names take precedence over everything else.
+    pub fn allow_host(mut self, domain: &str) -> HostPolicy {
+        self.allowed_names.push(normalize_domain(domain));
+        self
+    }
+
+    /// Denies the domain and its subdomains.
+    pub fn deny_host(mut self, domain: &str) -> HostPolicy {
+        self.denied_names.push(normalize_domain(domain));
+        self
+    }
+
+    /// Allows the addresses whose first `prefix` bits match the network, even if
+    /// their class is denied.
+    pub fn allow_network(mut self, network: IpAddr, prefix: u8) -> HostPolicy {
+        self.allowed_networks.push((network, prefix));
+        self
+    }
+
+    /// Denies the addresses whose first `prefix` bits match the network.
+    pub fn deny_network(mut self, network: IpAddr, prefix: u8) -> HostPolicy {
+        self.denied_networks.push((network, prefix));
+        self
+    }
+
+    /// Checks the host of a URI, including IPv4 addresses in the numeric forms
+    /// `Host::effective_ip_addr` recognizes.
+    pub fn check(&self, uri: &Uri) -> HostVerdict {
+        let host = match uri.host() {
+            Some(host) => host,
+            None => return HostVerdict::Deny(DenyReason::NoHost),
+        };
+        let name = trim_dot(&ascii_name(host));
+        if name.is_empty() {
+            return HostVerdict::Deny(DenyReason::NoHost);
+        }
+        if self.allowed_names.iter().any(|d| in_domain(&name, d)) {
+            return HostVerdict::Allow;
+        }
+        if self.denied_names.iter().any(|d| in_domain(&name, d)) {
+            return HostVerdict::Deny(DenyReason::Name(name));
+        }
+        match host.effective_ip_addr() {
+            Some(ip) => self.check_addr(ip),
+            None if is_localhost(&name) && self.denies(AddressClass::Loopback) => {
+                HostVerdict::Deny(DenyReason::NameClass(name, AddressClass::Loopback))
+            }
+            None => HostVerdict::Allow,
+        }
+    }
+
+    /// Checks an address, e.g. one a hostname resolved to.
+    pub fn check_addr(&self, ip: IpAddr) -> HostVerdict {
+        let net = |n: &(IpAddr, u8)| in_network(ip, n.0, n.1);
+        if self.allowed_networks.iter().any(net) {
+            return HostVerdict::Allow;
+        }
+        if self.denied_networks.iter().any(net) {
+            return HostVerdict::Deny(DenyReason::Network(ip));
+        }
+        let class = AddressClass::of(ip);
+        match self.denies(class) {
+            true => HostVerdict::Deny(DenyReason::Address(ip, class)),
+            false => HostVerdict::Allow,
+        }
+    }
+
+    fn denies(&self, class: AddressClass) -> bool {
+        self.denied_classes.contains(&class)
+    }
+}
+
+impl Default for HostPolicy {
+    fn default() -> HostPolicy {
+        HostPolicy::new()
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let ascii = idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_ascii_lowercase());
+    trim_dot(ascii.trim_start_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common_failures::prelude::*;
+
+    fn check(policy: &HostPolicy, uri: &str) -> Result<HostVerdict> {
+        Ok(policy.check(&Uri::parse(uri.as_bytes())?))
+    }
+
+    #[test]
+    fn test_address_class() -> Result<()> {
+        let classes = [
+            ("0.0.0.0", AddressClass::Unspecified),
+            ("127.0.0.1", AddressClass::Loopback),
+            ("10.1.2.3", AddressClass::Private),
+            ("172.16.0.1", AddressClass::Private),
+            ("172.31.255.255", AddressClass::Private),
+            ("172.32.0.1", AddressClass::Global),
+            ("192.168.1.1", AddressClass::Private),
+            ("100.64.0.1", AddressClass::SharedAddressSpace),
+            ("100.127.255.255", AddressClass::SharedAddressSpace),
+            ("100.128.0.1", AddressClass::Global),
+            ("169.254.1.1", AddressClass::LinkLocal),
+            ("169.254.169.254", AddressClass::Metadata),
+            ("100.100.100.200", AddressClass::Metadata),
+            ("224.0.0.1", AddressClass::Multicast),
+            ("240.0.0.1", AddressClass::Reserved),
+            ("255.255.255.255", AddressClass::Broadcast),
+            ("93.184.216.34", AddressClass::Global),
+            ("::", AddressClass::Unspecified),
+            ("::1", AddressClass::Loopback),
+            ("fd12::1", AddressClass::Private),
+            ("fe80::1", AddressClass::LinkLocal),
+            ("fd00:ec2::254", AddressClass::Metadata),
+            ("ff02::1", AddressClass::Multicast),
+            ("::ffff:127.0.0.1", AddressClass::Loopback),
+            ("::ffff:a9fe:a9fe", AddressClass::Metadata),
+            ("64:ff9b::10.0.0.1", AddressClass::Private),
+            ("2001:db8::1", AddressClass::Global),
+        ];
+        for (ip, class) in classes.iter() {
+            assert_eq!(*class, AddressClass::of(ip.parse()?), "{}", ip);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_predicates() -> Result<()> {
+        let host = |s: &str| -> Result<Host> {
+            let uri = Uri::parse(format!("http://{}/", s).as_bytes())?;
+            Ok(uri.host().cloned().unwrap())
+        };
+        assert_eq!(
+            Some("127.0.0.1".parse()?),
+            host("2130706433")?.effective_ip_addr()
+        );
+        assert_eq!(
+            Some("127.0.0.1".parse()?),
+            host("0x7f.1")?.effective_ip_addr()
+        );
+        assert_eq!(
+            Some("127.0.0.1".parse()?),
+            host("0177.0.0.1")?.effective_ip_addr()
+        );
+        assert_eq!(
+            Some("127.0.0.1".parse()?),
+            host("%31%32%37.0.0.1.")?.effective_ip_addr()
+        );
+        assert_eq!(None, host("127.0.0.1.example.com")?.effective_ip_addr());
+        assert_eq!(None, host("0x7f.example")?.effective_ip_addr());
+
+        assert!(host("LocalHost.")?.is_loopback());
+        assert!(host("api.localhost")?.is_loopback());
+        assert!(!host("localhost.example.com")?.is_loopback());
+        assert!(host("[::1]")?.is_loopback());
+        assert!(host("192.168.0.1")?.is_private());
+        assert!(host("100.64.1.1")?.is_shared_address_space());
+        assert!(host("169.254.169.254")?.is_link_local());
+        assert!(host("0xa9fea9fe")?.is_metadata());
+        assert!(host("metadata.google.internal")?.is_metadata());
+        assert!(host("239.1.1.1")?.is_multicast());
+        assert!(host("0")?.is_unspecified());
+        assert_eq!(None, host("example.com")?.address_class());
+
+        let uri = Uri::parse(b"http://10.0.0.1/")?;
+        match uri.host() {
+            Some(Host::IPv4address(ip)) => assert_eq!(AddressClass::Private, ip.class()),
+            h => panic!("{:?}", h),
+        }
+        let uri = Uri::parse(b"http://[fe80::1]/")?;
+        match uri.host() {
+            Some(Host::IPLiteral(IPLiteral::IPv6address(ip))) => {
+                assert_eq!(AddressClass::LinkLocal, ip.class())
+            }
+            h => panic!("{:?}", h),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_policy() -> Result<()> {
+        let policy = HostPolicy::default();
+        let allowed = [
+            "http://example.com/",
+            "https://93.184.216.34/",
+            "http://127.0.0.1.example.com/",
+            "http://[2001:db8::1]/",
+            "http://172.32.0.1/",
+        ];
+        for a in allowed.iter() {
+            assert_eq!(HostVerdict::Allow, check(&policy, a)?, "{}", a);
+        }
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let denied = [
+            ("mailto:a@example.com", DenyReason::NoHost),
+            ("file:///etc/passwd", DenyReason::NoHost),
+            (
+                "http://127.0.0.1/",
+                DenyReason::Address(ip("127.0.0.1"), AddressClass::Loopback),
+            ),
+            (
+                "http://2130706433/",
+                DenyReason::Address(ip("127.0.0.1"), AddressClass::Loopback),
+            ),
+            (
+                "http://0x7f.1/",
+                DenyReason::Address(ip("127.0.0.1"), AddressClass::Loopback),
+            ),
+            (
+                "http://0177.0.0.1/",
+                DenyReason::Address(ip("127.0.0.1"), AddressClass::Loopback),
+            ),
+            (
+                "http://[::ffff:127.0.0.1]/",
+                DenyReason::Address(ip("::ffff:127.0.0.1"), AddressClass::Loopback),
+            ),
+            (
+                "http://[::127.0.0.1]/",
+                DenyReason::Address(ip("::127.0.0.1"), AddressClass::Loopback),
+            ),
+            (
+                "http://[::ffff:0:7f00:1]/",
+                DenyReason::Address(ip("::ffff:0:7f00:1"), AddressClass::Loopback),
+            ),
+            (
+                "http://[2002:7f00:1::]/",
+                DenyReason::Address(ip("2002:7f00:1::"), AddressClass::Loopback),
+            ),
+            (
+                "http://[fec0::1]/",
+                DenyReason::Address(ip("fec0::1"), AddressClass::Private),
+            ),
+            (
+                "http://localhost:8080/",
+                DenyReason::NameClass("localhost".to_string(), AddressClass::Loopback),
+            ),
+            (
+                "http://10.0.0.1/",
+                DenyReason::Address(ip("10.0.0.1"), AddressClass::Private),
+            ),
+            (
+                "http://100.64.0.1/",
+                DenyReason::Address(ip("100.64.0.1"), AddressClass::SharedAddressSpace),
+            ),
+            (
+                "http://169.254.169.254/latest/meta-data/",
+                DenyReason::Address(ip("169.254.169.254"), AddressClass::Metadata),
+            ),
+            (
+                "http://0251.0376.0251.0376/",
+                DenyReason::Address(ip("169.254.169.254"), AddressClass::Metadata),
+            ),
+            (
+                "http://[fd00:ec2::254]/",
+                DenyReason::Address(ip("fd00:ec2::254"), AddressClass::Metadata),
+            ),
+            (
+                "http://Metadata.Google.Internal./",
+                DenyReason::Name("metadata.google.internal".to_string()),
+            ),
+            (
+                "http://[fe80::1%25eth0]/",
+                DenyReason::Address(ip("fe80::1"), AddressClass::LinkLocal),
+            ),
+            (
+                "http://224.0.0.1/",
+                DenyReason::Address(ip("224.0.0.1"), AddressClass::Multicast),
+            ),
+            (
+                "http://0/",
+                DenyReason::Address(ip("0.0.0.0"), AddressClass::Unspecified),
+            ),
+        ];
+        for (d, reason) in denied.iter() {
+            assert_eq!(
+                HostVerdict::Deny(reason.clone()),
+                check(&policy, d)?,
+                "{}",
+                d
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_configured_policy() -> Result<()> {
+        let policy = HostPolicy::new()
+            .allow_class(AddressClass::Private)
+            .deny_network("10.1.0.0".parse()?, 16)
+            .allow_network("127.0.0.1".parse()?, 32)
+            .allow_host("Internal.Example.")
+            .deny_host(".evil.example");
+
+        assert!(check(&policy, "http://192.168.1.1/")?.is_allowed());
+        assert!(check(&policy, "http://10.2.0.1/")?.is_allowed());
+        assert_eq!(
+            HostVerdict::Deny(DenyReason::Network("10.1.2.3".parse()?)),
+            check(&policy, "http://10.1.2.3/")?
+        );
+        assert!(check(&policy, "http://0x7f000001/")?.is_allowed());
+        assert!(!check(&policy, "http://127.0.0.2/")?.is_allowed());
+        assert!(check(&policy, "http://internal.example/")?.is_allowed());
+        assert!(check(&policy, "http://api.internal.example/")?.is_allowed());
+        assert_eq!(
+            HostVerdict::Deny(DenyReason::Name("a.evil.example".to_string())),
+            check(&policy, "http://A.EVIL.example/")?
+        );
+        assert!(check(&policy, "http://notevil.example/")?.is_allowed());
+
+        assert_eq!(
+            HostVerdict::Deny(DenyReason::Address(
+                "172.17.0.1".parse()?,
+                AddressClass::Private
+            )),
+            HostPolicy::new().check_addr("172.17.0.1".parse()?)
+        );
+        assert!(HostPolicy::new()
+            .allow_class(AddressClass::Loopback)
+            .check(&Uri::parse(b"http://localhost/")?)
+            .is_allowed());
+        assert_eq!(
+            "169.254.169.254 is a cloud metadata address.",
+            DenyReason::Address("169.254.169.254".parse()?, AddressClass::Metadata).to_string()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//https://tools.ietf.org/html/rfc6068
+
+use std::str::FromStr;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::percent_decode_str;
+use uri::Uri;
+
+/// A "mailto:" URI with its addresses and header fields decoded.
+///
+/// ```text
+/// mailtoURI = "mailto:" [ to ] [ hfields ]
+/// to        = addr-spec *("," addr-spec )
+/// hfields   = "?" hfield *( "&" hfield )
+/// hfield    = hfname "=" hfvalue
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MailtoUri {
+    to: Vec<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl MailtoUri {
+    /// Returns the view of a URI with the scheme "mailto". Error offsets are the ones
+    /// in the URI, those of addresses in "to" header fields the one of the value.
+    pub fn from_uri(uri: &Uri) -> Result<MailtoUri> {
+        let (opaque_part, offset) = uri.opaque_part_for("mailto")?;
+        parse_opaque_part(&opaque_part)
+            .map_err(|e| e.shifted(offset).with_input(uri.to_string().as_bytes()))
+    }
+
+    /// Returns the recipients, those before the header fields followed by those of
+    /// "to" header fields.
+    pub fn to(&self) -> impl Iterator<Item = &str> {
+        self.to.iter().map(|a| a.as_str())
+    }
+
+    /// Returns the decoded header fields in order, including "to", "subject" and
+    /// "body".
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Returns the value of the first header field with the given name, which is
+    /// compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.header("subject")
+    }
+
+    /// Returns the first text/plain body part, whose line breaks are "\r\n".
+    pub fn body(&self) -> Option<&str> {
+        self.header("body")
+    }
+}
+
+impl FromStr for MailtoUri {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<MailtoUri> {
+        MailtoUri::from_uri(&Uri::parse(s.as_bytes())?)
+    }
+}
+
+// Parses what follows "mailto:". Error offsets are the ones in it.
+fn parse_opaque_part(opaque_part: &str) -> Result<MailtoUri> {
+    let c = |i: usize| opaque_part.as_bytes().get(i).map(|b| Char::Ascii(*b));
+    let (to, hfields) = match opaque_part.find('?') {
+        Some(i) => (&opaque_part[..i], Some((i + 1, &opaque_part[i + 1..]))),
+        None => (opaque_part, None),
+    };
+
+    // The decoded addresses with the offsets they are reported at.
+    let mut addresses: Vec<(usize, String)> = Vec::new();
+    let mut start = 0;
+    for a in to.split(',').filter(|_| !to.is_empty()) {
+        let decoded = percent_decode_str(a).map_err(|e| e.shifted(start))?;
+        addresses.push((start, decoded.into_owned()));
+        start += a.len() + 1;
+    }
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    if let Some((mut start, hfields)) = hfields {
+        for hfield in hfields.split('&') {
+            let (name, value) = match hfield.find('=') {
+                Some(i) if i > 0 => (&hfield[..i], &hfield[i + 1..]),
+                _ => return Err(UriError::invalid(Production::HeaderField, start, c(start))),
+            };
+            let value_start = start + name.len() + 1;
+            let name = percent_decode_str(name).map_err(|e| e.shifted(start))?;
+            let value = percent_decode_str(value).map_err(|e| e.shifted(value_start))?;
+            if name.eq_ignore_ascii_case("to") {
+                addresses.extend(
+                    value
+                        .split(',')
+                        .map(|a| (value_start, a.trim().to_string())),
+                );
+            }
+            headers.push((name.into_owned(), value.into_owned()));
+            start += hfield.len() + 1;
+        }
+    }
+
+    for (start, a) in &addresses {
+        if !is_addr_spec(a) {
+            return Err(UriError::invalid(Production::Address, *start, c(*start)));
+        }
+    }
+    Ok(MailtoUri {
+        to: addresses.into_iter().map(|(_, a)| a).collect(),
+        headers,
+    })
+}
+
+// addr-spec = local-part "@" domain (RFC 5322, without comments and folding white
+// space as required by RFC 6068)
+fn is_addr_spec(addr_spec: &str) -> bool {
+    let at = match addr_spec.rfind('@') {
+        Some(at) => at,
+        None => return false,
+    };
+    let (local_part, domain) = (&addr_spec[..at], &addr_spec[at + 1..]);
+    let valid_local_part = is_dot_atom(local_part) || is_quoted_string(local_part);
+    let valid_domain = is_dot_atom(domain) || is_domain_literal(domain);
+    valid_local_part && valid_domain
+}
+
+// dot-atom-text = 1*atext *("." 1*atext)
+fn is_dot_atom(s: &str) -> bool {
+    let is_atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c);
+    !s.is_empty()
+        && s.split('.')
+            .all(|a| !a.is_empty() && a.chars().all(is_atext))
+}
+
+// quoted-string = DQUOTE *(qtext / quoted-pair) DQUOTE
+fn is_quoted_string(s: &str) -> bool {
+    if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+        return false;
+    }
+    let mut chars = s[1..s.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        let valid = match c {
+            '\\' => chars.next().is_some_and(|c| (' '..='~').contains(&c)),
+            '"' => false,
+            c => (' '..='~').contains(&c),
+        };
+        if !valid {
+            return false;
+        }
+    }
+    true
+}
+
+// domain-literal = "[" *dtext "]"
+fn is_domain_literal(s: &str) -> bool {
+    let is_dtext = |c: char| ('!'..='~').contains(&c) && !"[\\]".contains(c);
+    s.len() >= 2
+        && s.starts_with('[')
+        && s.ends_with(']')
+        && s[1..s.len() - 1].chars().all(is_dtext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::error::UriErrorKind;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let m: MailtoUri = "mailto:chris@example.com".parse()?;
+        assert_eq!(vec!["chris@example.com"], m.to().collect::<Vec<_>>());
+        assert_eq!(0, m.headers().count());
+
+        let m: MailtoUri =
+            "mailto:infobot@example.com?subject=current-issue&body=send%20current-issue%0D%0A"
+                .parse()?;
+        assert_eq!(Some("current-issue"), m.subject());
+        assert_eq!(Some("send current-issue\r\n"), m.body());
+
+        let m: MailtoUri = "MAILTO:joe@example.com,%22not%40me%22@example.org?to=bob@example.com,%20eve@%5B192.0.2.1%5D&Cc=x@y".parse()?;
+        assert_eq!(
+            vec![
+                "joe@example.com",
+                "\"not@me\"@example.org",
+                "bob@example.com",
+                "eve@[192.0.2.1]"
+            ],
+            m.to().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("x@y"), m.header("cc"));
+
+        let m: MailtoUri = "mailto:?to=%22%5C%22%22@example.com&subject=caf%C3%A9".parse()?;
+        assert_eq!(vec!["\"\\\"\"@example.com"], m.to().collect::<Vec<_>>());
+        assert_eq!(Some("café"), m.subject());
+
+        let m: MailtoUri = "mailto:?subject=hello".parse()?;
+        assert_eq!(0, m.to().count());
+        Ok(())
+    }
+
+    fn parse_error(input: &str) -> (UriErrorKind, Production, usize) {
+        let e = input.parse::<MailtoUri>().unwrap_err();
+        (e.kind(), e.production(), e.offset())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use uri::error::UriErrorKind::*;
+
+        assert_eq!(
+            (NotAllowed, Production::Scheme, 0),
+            parse_error("http://a/")
+        );
+        assert_eq!(
+            (NotAllowed, Production::Authority, 9),
+            parse_error("mailto://a@b")
+        );
+        assert_eq!(
+            (Invalid, Production::Address, 7),
+            parse_error("mailto:nobody")
+        );
+        assert_eq!(
+            (Invalid, Production::Address, 11),
+            parse_error("mailto:a@b,")
+        );
+        assert_eq!(
+            (Invalid, Production::Address, 11),
+            parse_error("mailto:a@b,a..b@c")
+        );
+        assert_eq!(
+            (Invalid, Production::Address, 7),
+            parse_error("mailto:%22a%22b%22@c")
+        );
+        assert_eq!(
+            (Invalid, Production::HeaderField, 11),
+            parse_error("mailto:a@b?subject")
+        );
+        assert_eq!(
+            (Invalid, Production::HeaderField, 15),
+            parse_error("mailto:a@b?a=1&=x")
+        );
+        assert_eq!(
+            (Invalid, Production::Address, 14),
+            parse_error("mailto:a@b?to=x@y,c")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Escape, 20),
+            parse_error("mailto:a@b?subject=%G0")
+        );
+        assert_eq!(
+            (Utf8, Production::Escape, 19),
+            parse_error("mailto:a@b?subject=%FF")
+        );
+        assert_eq!(
+            "Invalid header field at byte 11.\nmailto:a@b?subject\n           ^",
+            "mailto:a@b?subject"
+                .parse::<MailtoUri>()
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//https://tools.ietf.org/html/rfc3986#section-6
+
+use uri::char_stream::Char;
+use uri::host;
+use uri::resolve::remove_dot_segments;
+use uri::token_buffer::TokenBuffer;
+use uri::Authority;
+use uri::Fragment;
+use uri::Host;
+use uri::Hostport;
+use uri::IPLiteral;
+use uri::IPv6address;
+use uri::IPvFuture;
+use uri::Param;
+use uri::PathSegments;
+use uri::Query;
+use uri::Scheme;
+use uri::Segment;
+use uri::Uri;
+use uri::Userinfo;
+use uri::ZoneId;
+
+impl Uri {
+    /// Returns the normal form of this URI, applying the syntax-based
+    /// normalization of RFC 3986 (case, percent-encoding and dot-segments) and
+    /// the scheme-based normalization of default ports and empty paths.
+    pub fn normalize(&self) -> Uri {
+        let scheme = self
+            .scheme
+            .as_ref()
+            .map(|s| Scheme(s.0.iter().map(|c| c.to_ascii_lowercase()).collect()));
+        let authority = self
+            .authority
+            .as_ref()
+            .map(|a| normalize_authority(a, scheme.as_ref()));
+
+        let mut path = PathSegments {
+            segments: self.path.segments.iter().map(normalize_segment).collect(),
+        };
+        if scheme.is_some() || path.is_absolute() {
+            path = remove_dot_segments(&path);
+        }
+        if authority.is_some() && path.is_empty() {
+            path.segments.push(Segment::new());
+        }
+
+        Uri {
+            scheme,
+            authority,
+            path,
+            query: self.query.as_ref().map(|q| Query(normalize_chars(&q.0))),
+            fragment: self
+                .fragment
+                .as_ref()
+                .map(|f| Fragment(normalize_chars(&f.0))),
+        }
+    }
+
+    /// Returns whether both URIs are equal after normalization.
+    pub fn equivalent(&self, other: &Uri) -> bool {
+        self.normalize() == other.normalize()
+    }
+}
+
+/// A URI in normal form, so equivalent URIs are equal and have the same hash, e.g.
+/// as keys of a `HashMap`. `Uri` itself compares and hashes its components as they
+/// are.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NormalizedUri(Uri);
+
+impl NormalizedUri {
+    pub fn new(u: &Uri) -> NormalizedUri {
+        NormalizedUri(u.normalize())
+    }
+
+    pub fn as_uri(&self) -> &Uri {
+        &self.0
+    }
+
+    pub fn into_uri(self) -> Uri {
+        self.0
+    }
+}
+
+impl From<Uri> for NormalizedUri {
+    fn from(u: Uri) -> NormalizedUri {
+        NormalizedUri::new(&u)
+    }
+}
+
+impl std::fmt::Display for NormalizedUri {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+fn normalize_chars(chars: &[Char]) -> Vec<Char> {
+    chars.iter().map(Char::normalize).collect()
+}
+
+fn normalize_authority(authority: &Authority, scheme: Option<&Scheme>) -> Authority {
+    let mut port = authority.hostport.1.clone();
+    if let Some(p) = &port {
+        let value = p.to_string().parse::<u16>().ok();
+        if p.0.is_empty() || (value.is_some() && value == scheme.and_then(|s| s.default_port())) {
+            port = None;
+        }
+    }
+    Authority {
+        userinfo: authority
+            .userinfo
+            .as_ref()
+            .map(|u| Userinfo(normalize_chars(&u.0))),
+        hostport: Hostport(normalize_host(&authority.hostport.0), port),
+    }
+}
+
+fn normalize_host(h: &Host) -> Host {
+    let chars: Vec<Char> = match h {
+        Host::IPLiteral(IPLiteral::IPv6address(ip)) => {
+            return Host::IPLiteral(IPLiteral::IPv6address(IPv6address {
+                tokens: ip.tokens.iter().map(|c| c.to_ascii_lowercase()).collect(),
+                addr: ip.addr,
+                zone_id: ip.zone_id.as_ref().map(|z| ZoneId(normalize_chars(&z.0))),
+            }));
+        }
+        Host::IPLiteral(IPLiteral::IPvFuture(f)) => {
+            let lowercase = |c: &Vec<Char>| c.iter().map(|c| c.to_ascii_lowercase()).collect();
+            return Host::IPLiteral(IPLiteral::IPvFuture(IPvFuture(
+                f.0.to_ascii_lowercase(),
+                lowercase(&f.1),
+                lowercase(&f.2),
+            )));
+        }
+        Host::IPv4address(ip) => {
+            return Host::IPv4address(ip.clone());
+        }
+        Host::Hostname(hn) => {
+            let mut c: Vec<Char> = Vec::new();
+            for dl in &hn.0 {
+                c.extend(dl.0.iter());
+                c.push(Char::Ascii(b'.'));
+            }
+            c.extend(hn.1 .0.iter());
+            c.extend(hn.2.iter());
+            c
+        }
+        Host::RegName(rn) => rn.0.clone(),
+    };
+
+    // Decoding may turn a reg-name into a hostname or an IPv4 address.
+    let chars = chars
+        .iter()
+        .map(|c| c.normalize().to_ascii_lowercase())
+        .collect::<Vec<Char>>();
+    match host(&mut TokenBuffer::from(chars)) {
+        Ok(h) => h,
+        Err(_) => h.clone(),
+    }
+}
+
+fn normalize_segment(s: &Segment) -> Segment {
+    Segment {
+        pchars: normalize_chars(&s.pchars),
+        params: s.params.as_ref().map(|ps| {
+            ps.iter()
+                .map(|p| Param {
+                    pchars: normalize_chars(&p.pchars),
+                })
+                .collect()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common_failures::prelude::*;
+
+    fn parse(s: &str) -> Result<Uri> {
+        Ok(Uri::parse(s.as_bytes())?)
+    }
+
+    fn normalize(s: &str) -> Result<String> {
+        Ok(parse(s)?.normalize().to_string())
+    }
+
+    #[test]
+    fn test_normalize() -> Result<()> {
+        assert_eq!(
+            "http://example.com/~user",
+            normalize("HTTP://Example.COM:80/%7euser")?
+        );
+        assert_eq!("http://example.com/", normalize("http://example.com")?);
+        assert_eq!("http://example.com/", normalize("http://example.com:/")?);
+        assert_eq!("https://a/", normalize("https://a:443")?);
+        assert_eq!("https://a:80/", normalize("https://a:80")?);
+        assert_eq!("http://a/b/%2F%C3%A4", normalize("http://a/b/%2f%c3%a4")?);
+        assert_eq!("http://a/g;x=~", normalize("http://a/b/../g;x=%7E")?);
+        assert_eq!(
+            "http://a/b?~%3D#~%2F",
+            normalize("http://a/b?%7e%3d#%7e%2f")?
+        );
+        assert_eq!("http://User%3A@a/", normalize("http://User%3a@a/")?);
+        assert_eq!("http://[fe80::1]/", normalize("http://[FE80::1]/")?);
+        assert_eq!("http://127.0.0.1/", normalize("http://%31%32%37.0.0.1/")?);
+        assert_eq!("mailto:A@B.com", normalize("MAILTO:A@B.com")?);
+        assert_eq!("../a", normalize("../a")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_host_type() -> Result<()> {
+        let u = parse("http://%31%32%37.0.0.1/")?.normalize();
+        match u.host() {
+            Some(Host::IPv4address(_)) => {}
+            h => panic!("Unexpected host {:?}", h),
+        }
+        let u = parse("http://Ex%61mple.COM/")?.normalize();
+        match u.host() {
+            Some(Host::Hostname(hn)) => assert_eq!("example.com", hn.to_string()),
+            h => panic!("Unexpected host {:?}", h),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_equivalent() -> Result<()> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        use std::hash::Hash;
+        use std::hash::Hasher;
+
+        let a = parse("HTTP://Example.COM:80/%7euser")?;
+        let b = parse("http://example.com/~user")?;
+        assert!(!(a == b));
+        assert!(a.equivalent(&b));
+        assert!(!a.equivalent(&parse("http://example.com/~User")?));
+
+        fn hash<T: Hash>(t: &T) -> u64 {
+            let mut h = DefaultHasher::new();
+            t.hash(&mut h);
+            h.finish()
+        }
+        assert_eq!(hash(&a), hash(&a.clone()));
+        assert_eq!(hash(&NormalizedUri::new(&a)), hash(&NormalizedUri::new(&b)));
+        assert_eq!(NormalizedUri::new(&a), NormalizedUri::from(b.clone()));
+        assert_eq!(
+            "http://example.com/~user",
+            NormalizedUri::new(&a).to_string()
+        );
+
+        let mut set: HashSet<NormalizedUri> = HashSet::new();
+        set.insert(a.clone().into());
+        assert!(set.contains(&NormalizedUri::new(&b)));
+        assert!(!set.contains(&parse("http://example.com/~User")?.into()));
+
+        let mut set: HashSet<Uri> = HashSet::new();
+        set.insert(a.clone());
+        assert!(set.contains(&a));
+        assert!(!set.contains(&b));
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+//https://tools.ietf.org/html/rfc3986#section-2.1
+
+use std::borrow::Cow;
+use std::fmt::Write;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::Fragment;
+use uri::Param;
+use uri::PathSegments;
+use uri::Query;
+use uri::RegName;
+use uri::Segment;
+use uri::Userinfo;
+
+/// The characters a component may contain unescaped. Everything else is
+/// percent-encoded by `percent_encode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodeSet {
+    /// A single path segment: pchar without ";", which starts a parameter.
+    PathSegment,
+    /// pchar, "/" and "?".
+    Query,
+    /// pchar, "/" and "?".
+    Fragment,
+    /// unreserved, sub-delims and ":".
+    Userinfo,
+    /// A registered name: unreserved and sub-delims.
+    Host,
+    /// A key or value of application/x-www-form-urlencoded data: alphanumerics
+    /// and "*-._", with " " written as "+".
+    Form,
+}
+
+impl EncodeSet {
+    pub(crate) fn allows(self, b: u8) -> bool {
+        let c = Char::Ascii(b);
+        match self {
+            EncodeSet::PathSegment => c.is_pchar() && b != b';',
+            EncodeSet::Query | EncodeSet::Fragment => c.is_pchar() || b == b'/' || b == b'?',
+            EncodeSet::Userinfo => c.is_unreserved() || c.is_sub_delim() || b == b':',
+            EncodeSet::Host => c.is_unreserved() || c.is_sub_delim(),
+            EncodeSet::Form => c.is_alphanum() || b"*-._".contains(&b),
+        }
+    }
+}
+
+/// Percent-encodes every byte of `input` that `set` does not allow, borrowing
+/// the input if nothing needs encoding.
+pub fn percent_encode(input: &str, set: EncodeSet) -> Cow<'_, str> {
+    if input.bytes().all(|b| set.allows(b)) {
+        return Cow::Borrowed(input);
+    }
+    let mut encoded = String::with_capacity(input.len() * 3);
+    for b in input.bytes() {
+        if set.allows(b) {
+            encoded.push(b as char);
+        } else if b == b' ' && set == EncodeSet::Form {
+            encoded.push('+');
+        } else {
+            write!(encoded, "%{:02X}", b).unwrap();
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+/// Decodes all escapes in `input`, borrowing the input if there are none.
+pub fn percent_decode(input: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if !input.contains(&b'%') {
+        return Ok(Cow::Borrowed(input));
+    }
+    let mut decoded: Vec<u8> = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != b'%' {
+            decoded.push(input[i]);
+            i += 1;
+            continue;
+        }
+        for j in i + 1..i + 3 {
+            match input.get(j) {
+                Some(h) if h.is_ascii_hexdigit() => {}
+                h => {
+                    let c = h.map(|h| Char::Ascii(*h));
+                    return Err(UriError::unexpected(Production::Escape, j, c));
+                }
+            }
+        }
+        decoded.push(hex_pair(input[i + 1], input[i + 2]).unwrap());
+        i += 3;
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+/// Decodes all escapes in `input` and validates the result as UTF-8.
+pub fn percent_decode_str(input: &str) -> Result<Cow<'_, str>> {
+    match percent_decode(input.as_bytes())? {
+        Cow::Borrowed(_) => Ok(Cow::Borrowed(input)),
+        Cow::Owned(bytes) => Ok(Cow::Owned(utf8(bytes, Production::Escape)?)),
+    }
+}
+
+// Converts an already percent-encoded string into characters, e.g. the output of
+// `percent_encode`.
+pub(crate) fn encoded_chars(encoded: &str) -> Vec<Char> {
+    let bytes = encoded.as_bytes();
+    let mut chars: Vec<Char> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && hex_pair(bytes[i + 1], bytes[i + 2]).is_some()
+        {
+            chars.push(Char::Escaped((b'%', bytes[i + 1], bytes[i + 2])));
+            i += 3;
+        } else {
+            chars.push(Char::Ascii(bytes[i]));
+            i += 1;
+        }
+    }
+    chars
+}
+
+fn hex_pair(h: u8, l: u8) -> Option<u8> {
+    let value = |b: u8| (b as char).to_digit(16);
+    match (value(h), value(l)) {
+        (Some(h), Some(l)) => Some((h << 4 | l) as u8),
+        _ => None,
+    }
+}
+
+fn utf8(bytes: Vec<u8>, production: Production) -> Result<String> {
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(s),
+        Err(e) => {
+            let offset = e.utf8_error().valid_up_to();
+            let byte = e.as_bytes()[offset];
+            Err(UriError::new(
+                UriErrorKind::Utf8,
+                production,
+                offset,
+                Some(byte),
+            ))
+        }
+    }
+}
+
+fn decode_chars<'a, I>(chars: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a Char>,
+{
+    chars.into_iter().map(Char::byte).collect()
+}
+
+impl Userinfo {
+    /// Returns the userinfo with all escapes decoded.
+    pub fn decode(&self) -> Vec<u8> {
+        decode_chars(&self.0)
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Userinfo)
+    }
+}
+
+impl RegName {
+    /// Returns the registered name with all escapes decoded.
+    pub fn decode(&self) -> Vec<u8> {
+        decode_chars(&self.0)
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Host)
+    }
+}
+
+impl PathSegments {
+    /// Returns the path with all escapes decoded. Note that an escaped "/"
+    /// can no longer be told apart from a segment separator.
+    pub fn decode(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        for (i, s) in self.segments.iter().enumerate() {
+            if i > 0 {
+                bytes.push(b'/');
+            }
+            bytes.extend(s.decode_with_params());
+        }
+        bytes
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Path)
+    }
+}
+
+impl Segment {
+    /// Returns the segment without its parameters, with all escapes decoded.
+    pub fn decode(&self) -> Vec<u8> {
+        decode_chars(&self.pchars)
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Path)
+    }
+
+    pub(crate) fn decode_with_params(&self) -> Vec<u8> {
+        let mut bytes = self.decode();
+        if let Some(params) = &self.params {
+            for p in params {
+                bytes.push(b';');
+                bytes.extend(p.decode());
+            }
+        }
+        bytes
+    }
+}
+
+impl Param {
+    /// Returns the parameter with all escapes decoded.
+    pub fn decode(&self) -> Vec<u8> {
+        decode_chars(&self.pchars)
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Path)
+    }
+}
+
+impl Query {
+    /// Returns the query with all escapes decoded.
+    pub fn decode(&self) -> Vec<u8> {
+        decode_chars(&self.0)
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Query)
+    }
+}
+
+impl Fragment {
+    /// Returns the fragment with all escapes decoded.
+    pub fn decode(&self) -> Vec<u8> {
+        decode_chars(&self.0)
+    }
+
+    pub fn decode_utf8(&self) -> Result<String> {
+        utf8(self.decode(), Production::Fragment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::Host;
+    use uri::Uri;
+
+    #[test]
+    fn test_percent_encode() -> Result<()> {
+        assert_eq!(
+            Cow::Borrowed("abc"),
+            percent_encode("abc", EncodeSet::PathSegment)
+        );
+        assert_eq!(
+            "a%20b%2Fc%3Bd%3F:@",
+            percent_encode("a b/c;d?:@", EncodeSet::PathSegment)
+        );
+        assert_eq!("a%20b/c;d?", percent_encode("a b/c;d?", EncodeSet::Query));
+        assert_eq!("%23x%C3%A4", percent_encode("#xä", EncodeSet::Fragment));
+        assert_eq!("us:er%40", percent_encode("us:er@", EncodeSet::Userinfo));
+        assert_eq!("a%3Ab%C3%A4", percent_encode("a:bä", EncodeSet::Host));
+        assert_eq!(
+            "a+b%26c%3Dd%2B*",
+            percent_encode("a b&c=d+*", EncodeSet::Form)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_percent_decode() -> Result<()> {
+        assert_eq!(Cow::Borrowed(&b"abc"[..]), percent_decode(b"abc")?);
+        assert_eq!(
+            b"a b/\xff".to_vec(),
+            percent_decode(b"a%20b%2f%FF")?.to_vec()
+        );
+        assert_eq!(
+            "Unexpected character 'z' in escape sequence at byte 2.",
+            percent_decode(b"a%zz").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Unexpected end of escape sequence at byte 3.",
+            percent_decode(b"a%2").unwrap_err().to_string()
+        );
+
+        assert_eq!("xä y", percent_decode_str("x%C3%A4%20y")?);
+        assert_eq!(
+            "Invalid UTF-8 in decoded escape sequence at byte 1.",
+            percent_decode_str("x%C3").unwrap_err().to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<()> {
+        for set in [
+            EncodeSet::PathSegment,
+            EncodeSet::Query,
+            EncodeSet::Fragment,
+            EncodeSet::Userinfo,
+            EncodeSet::Host,
+        ]
+        .iter()
+        {
+            let s = "a b/c?d#e%f;g=h&i+jä";
+            assert_eq!(s, percent_decode_str(&percent_encode(s, *set))?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_components() -> Result<()> {
+        let u: Uri = "http://u%40@h%20/a%2Fb;p%3D?q%C3%A4#f%FF".parse()?;
+
+        assert_eq!("u@", u.userinfo().unwrap().decode_utf8()?);
+        match u.host() {
+            Some(Host::RegName(rn)) => assert_eq!("h ", rn.decode_utf8()?),
+            h => panic!("Unexpected host {:?}", h),
+        }
+        assert_eq!("/a/b;p=", u.path().decode_utf8()?);
+        assert_eq!("a/b", u.path().segments[1].decode_utf8()?);
+        assert_eq!(
+            "p=",
+            u.path().segments[1].params.as_ref().unwrap()[0].decode_utf8()?
+        );
+        assert_eq!("qä", u.query().unwrap().decode_utf8()?);
+        assert_eq!(b"f\xff".to_vec(), u.fragment().unwrap().decode());
+        assert!(u.fragment().unwrap().decode_utf8().is_err());
+        Ok(())
+    }
+}
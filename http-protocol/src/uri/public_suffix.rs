@@ -0,0 +1,345 @@
+//https://publicsuffix.org/list/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+use uri::Hostname;
+
+// A copy of https://publicsuffix.org/list/public_suffix_list.dat. Replace the file
+// to update the list that is built in, or load a newer one at runtime with
+// `set_public_suffix_list`.
+const EMBEDDED_LIST: &str = include_str!("public_suffix_list.dat");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rule {
+    Normal,
+    Exception,
+}
+
+/// The rules of a Public Suffix List, with normal ("com"), wildcard ("*.ck") and
+/// exception ("!www.ck") rules from both the ICANN and the private section.
+#[derive(Clone, Debug)]
+pub struct PublicSuffixList {
+    root: Node,
+}
+
+// The rules as a tree of labels from the right, so "*.kobe.jp" is the child "*" of
+// the child "kobe" of the child "jp" of the root.
+#[derive(Clone, Debug, Default)]
+struct Node {
+    rule: Option<Rule>,
+    children: HashMap<String, Node>,
+}
+
+/// The error of `PublicSuffixList::parse`: a rule that is no domain name, with the
+/// line it is on, counted from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicSuffixError {
+    line: usize,
+    rule: String,
+}
+
+impl PublicSuffixError {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+}
+
+impl std::fmt::Display for PublicSuffixError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Invalid public suffix rule '{}' on line {}.",
+            self.rule, self.line
+        )
+    }
+}
+
+impl std::error::Error for PublicSuffixError {}
+
+impl PublicSuffixList {
+    /// Parses a list in the format of "public_suffix_list.dat": one rule per line,
+    /// up to the first whitespace, and comments starting with "//". Internationalized
+    /// rules are stored as A-labels.
+    pub fn parse(list: &str) -> Result<PublicSuffixList, PublicSuffixError> {
+        let mut root = Node::default();
+        for (i, line) in list.lines().enumerate() {
+            let rule = line.split_whitespace().next().unwrap_or("");
+            if rule.is_empty() || rule.starts_with("//") {
+                continue;
+            }
+            let (name, kind) = match rule.strip_prefix('!') {
+                Some(name) => (name, Rule::Exception),
+                None => (rule, Rule::Normal),
+            };
+            let (wildcard, domain) = match name.strip_prefix("*.") {
+                Some(domain) => (Some("*"), domain),
+                None => (None, name),
+            };
+            let ascii = match idna::domain_to_ascii(domain) {
+                Ok(ascii) if !ascii.is_empty() && !ascii.contains('*') => ascii,
+                _ => {
+                    return Err(PublicSuffixError {
+                        line: i + 1,
+                        rule: rule.to_string(),
+                    })
+                }
+            };
+            let mut node = &mut root;
+            for label in ascii.rsplit('.').chain(wildcard) {
+                node = node.children.entry(label.to_string()).or_default();
+            }
+            node.rule = Some(kind);
+        }
+        Ok(PublicSuffixList { root })
+    }
+
+    /// Returns the public suffix of a lowercase ASCII domain without a trailing dot,
+    /// e.g. "co.uk" for "www.example.co.uk". A domain no rule matches has its top
+    /// label as public suffix.
+    pub fn public_suffix<'a>(&self, domain: &'a str) -> &'a str {
+        let n = self.suffix_labels(domain.rsplit('.'));
+        &domain[label_start(domain, n)..]
+    }
+
+    /// Returns the public suffix and the label before it, e.g. "example.co.uk" for
+    /// "www.example.co.uk", or None if the domain is a public suffix itself.
+    pub fn registrable_domain<'a>(&self, domain: &'a str) -> Option<&'a str> {
+        let n = self.suffix_labels(domain.rsplit('.')) + 1;
+        match n > domain.split('.').count() {
+            true => None,
+            false => Some(&domain[label_start(domain, n)..]),
+        }
+    }
+
+    // Returns the number of labels of the public suffix of the labels, which are
+    // given from the right. Exception rules win over all others, otherwise the rule
+    // with the most labels wins.
+    fn suffix_labels<I, S>(&self, labels: I) -> usize
+    where
+        I: Iterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut longest = 1;
+        let mut node = &self.root;
+        for (n, label) in labels.enumerate() {
+            if let Some(Rule::Normal) = node.children.get("*").and_then(|w| w.rule) {
+                longest = n + 1;
+            }
+            node = match node.children.get(label.as_ref()) {
+                Some(child) => child,
+                None => break,
+            };
+            match node.rule {
+                Some(Rule::Exception) => return n,
+                Some(Rule::Normal) => longest = n + 1,
+                None => {}
+            }
+        }
+        longest
+    }
+}
+
+// Returns the offset of the last `n` labels of the domain.
+fn label_start(domain: &str, n: usize) -> usize {
+    match domain.rmatch_indices('.').nth(n - 1) {
+        Some((i, _)) => i + 1,
+        None => 0,
+    }
+}
+
+fn registry() -> &'static RwLock<Arc<PublicSuffixList>> {
+    static REGISTRY: OnceLock<RwLock<Arc<PublicSuffixList>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let list = PublicSuffixList::parse(EMBEDDED_LIST).unwrap();
+        RwLock::new(Arc::new(list))
+    })
+}
+
+/// Returns the list `Hostname` uses, which is the embedded one unless it was
+/// replaced.
+pub fn public_suffix_list() -> Arc<PublicSuffixList> {
+    let list = registry().read().unwrap_or_else(|e| e.into_inner());
+    list.clone()
+}
+
+/// Replaces the list `Hostname` uses for the whole process, e.g. with a newer
+/// download.
+pub fn set_public_suffix_list(list: PublicSuffixList) {
+    let mut current = registry().write().unwrap_or_else(|e| e.into_inner());
+    *current = Arc::new(list);
+}
+
+impl Hostname {
+    // Returns the lowercase labels from the right, without the trailing dot.
+    fn labels_from_right(&self) -> impl Iterator<Item = String> + '_ {
+        let top = std::iter::once(&self.1 .0);
+        top.chain(self.0.iter().rev().map(|dl| &dl.0)).map(|chars| {
+            chars
+                .iter()
+                .map(|c| c.first_byte().to_ascii_lowercase() as char)
+                .collect()
+        })
+    }
+
+    // Returns the last `n` labels in lowercase, or None if there are fewer.
+    fn last_labels(&self, n: usize) -> Option<String> {
+        let mut labels: Vec<String> = self.labels_from_right().take(n).collect();
+        if labels.len() < n {
+            return None;
+        }
+        labels.reverse();
+        Some(labels.join("."))
+    }
+
+    /// Returns the public suffix (eTLD) in lowercase, e.g. "co.uk" for
+    /// "WWW.Example.co.uk.".
+    pub fn public_suffix(&self) -> String {
+        let n = public_suffix_list().suffix_labels(self.labels_from_right());
+        self.last_labels(n).unwrap_or_default()
+    }
+
+    /// Returns the registrable domain (eTLD+1) in lowercase, e.g. "example.co.uk"
+    /// for "WWW.Example.co.uk.", or None if the hostname is a public suffix.
+    pub fn registrable_domain(&self) -> Option<String> {
+        let n = public_suffix_list().suffix_labels(self.labels_from_right());
+        self.last_labels(n + 1)
+    }
+}
+
+/// Returns whether two hostnames are same site: they have the same registrable
+/// domain, or are the same public suffix.
+pub fn is_same_site(a: &Hostname, b: &Hostname) -> bool {
+    match (a.registrable_domain(), b.registrable_domain()) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => a.labels_from_right().eq(b.labels_from_right()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::error::Result;
+    use uri::Host;
+    use uri::Uri;
+
+    fn hostname(s: &str) -> Result<Hostname> {
+        match Uri::parse(format!("http://{}/", s).as_bytes())?.host() {
+            Some(Host::Hostname(hn)) => Ok(hn.clone()),
+            h => panic!("'{}' is not a hostname: {:?}", s, h),
+        }
+    }
+
+    #[test]
+    fn test_embedded_list() -> Result<()> {
+        // From https://github.com/publicsuffix/list/blob/master/tests/test_psl.txt
+        let cases = [
+            ("com", "com", None),
+            ("example.com", "com", Some("example.com")),
+            ("www.example.com", "com", Some("example.com")),
+            ("example.uk.com", "uk.com", Some("example.uk.com")),
+            ("example.example", "example", Some("example.example")),
+            ("b.example.local", "local", Some("example.local")),
+            ("biz", "biz", None),
+            ("a.b.example.uk.com", "uk.com", Some("example.uk.com")),
+            ("jp", "jp", None),
+            ("test.jp", "jp", Some("test.jp")),
+            ("ac.jp", "ac.jp", None),
+            ("www.test.ac.jp", "ac.jp", Some("test.ac.jp")),
+            ("kyoto.jp", "kyoto.jp", None),
+            ("a.ide.kyoto.jp", "ide.kyoto.jp", Some("a.ide.kyoto.jp")),
+            ("c.kobe.jp", "c.kobe.jp", None),
+            ("b.c.kobe.jp", "c.kobe.jp", Some("b.c.kobe.jp")),
+            ("city.kobe.jp", "kobe.jp", Some("city.kobe.jp")),
+            ("www.city.kobe.jp", "kobe.jp", Some("city.kobe.jp")),
+            ("ck", "ck", None),
+            ("test.ck", "test.ck", None),
+            ("b.test.ck", "test.ck", Some("b.test.ck")),
+            ("www.ck", "ck", Some("www.ck")),
+            ("www.www.ck", "ck", Some("www.ck")),
+            ("us", "us", None),
+            ("www.test.us", "us", Some("test.us")),
+            ("test.ak.us", "ak.us", Some("test.ak.us")),
+            ("www.test.k12.ak.us", "k12.ak.us", Some("test.k12.ak.us")),
+            ("xn--85x722f.com.cn", "com.cn", Some("xn--85x722f.com.cn")),
+            (
+                "www.xn--55qx5d.cn",
+                "xn--55qx5d.cn",
+                Some("www.xn--55qx5d.cn"),
+            ),
+            ("shishi.xn--fiqs8s", "xn--fiqs8s", Some("shishi.xn--fiqs8s")),
+            ("foo.github.io", "github.io", Some("foo.github.io")),
+        ];
+        for (domain, suffix, registrable) in cases.iter() {
+            let hn = hostname(domain)?;
+            assert_eq!(*suffix, hn.public_suffix(), "{}", domain);
+            assert_eq!(
+                registrable.map(|r| r.to_string()),
+                hn.registrable_domain(),
+                "{}",
+                domain
+            );
+        }
+
+        let hn = hostname("WWW.Example.CO.UK.")?;
+        assert_eq!("co.uk", hn.public_suffix());
+        assert_eq!(Some("example.co.uk".to_string()), hn.registrable_domain());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_same_site() -> Result<()> {
+        let same = [
+            ("example.com", "www.example.com"),
+            ("a.example.co.uk", "B.Example.co.uk."),
+            ("co.uk", "CO.UK"),
+        ];
+        for (a, b) in same.iter() {
+            assert!(is_same_site(&hostname(a)?, &hostname(b)?), "{} {}", a, b);
+        }
+        let different = [
+            ("example.com", "example.org"),
+            ("a.github.io", "b.github.io"),
+            ("example.co.uk", "co.uk"),
+            ("co.uk", "org.uk"),
+        ];
+        for (a, b) in different.iter() {
+            assert!(!is_same_site(&hostname(a)?, &hostname(b)?), "{} {}", a, b);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> std::result::Result<(), PublicSuffixError> {
+        let list = PublicSuffixList::parse(
+            "// comment\n\ncom\n*.example.com  trailing text\n!www.example.com\nbücher.example\n",
+        )?;
+        assert_eq!("com", list.public_suffix("a.com"));
+        assert_eq!("b.example.com", list.public_suffix("a.b.example.com"));
+        assert_eq!("example.com", list.public_suffix("www.example.com"));
+        assert_eq!(
+            "xn--bcher-kva.example",
+            list.public_suffix("a.xn--bcher-kva.example")
+        );
+        assert_eq!("org", list.public_suffix("a.org"));
+        assert_eq!(None, list.registrable_domain("org"));
+        assert_eq!(Some("a.org"), list.registrable_domain("x.a.org"));
+
+        let e = PublicSuffixList::parse("com\n// comment\n\n a.*.com x").unwrap_err();
+        assert_eq!(4, e.line());
+        assert_eq!("a.*.com", e.rule());
+        assert_eq!(
+            "Invalid public suffix rule 'a.*.com' on line 4.",
+            e.to_string()
+        );
+        assert_eq!(1, PublicSuffixList::parse("!").unwrap_err().line());
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//https://url.spec.whatwg.org/#application/x-www-form-urlencoded
+
+use std::fmt::Write;
+use std::iter::FromIterator;
+use uri::char_stream::Char;
+use uri::percent::encoded_chars;
+use uri::percent_encode;
+use uri::EncodeSet;
+use uri::Query;
+use uri::Uri;
+
+/// The decoded `key=value` pairs of an application/x-www-form-urlencoded query,
+/// in order and with repeated keys kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryPairs {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryPairs {
+    pub fn new() -> QueryPairs {
+        QueryPairs { pairs: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the value of the first pair with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the values of all pairs with the given key.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.pairs
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.pairs.iter().any(|(k, _)| k == key)
+    }
+
+    pub fn append(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_string(), value.to_string()));
+    }
+
+    /// Replaces the first pair with the given key and removes all others, or
+    /// appends the pair if the key is not present.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.pairs.iter().position(|(k, _)| k == key) {
+            Some(i) => {
+                self.pairs[i].1 = value.to_string();
+                let mut j = i + 1;
+                while j < self.pairs.len() {
+                    match self.pairs[j].0 == key {
+                        true => {
+                            self.pairs.remove(j);
+                        }
+                        false => j += 1,
+                    }
+                }
+            }
+            None => self.append(key, value),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.pairs.retain(|(k, _)| k != key);
+    }
+
+    /// Returns the pairs serialized as a query.
+    pub fn to_query(&self) -> Query {
+        Query(encoded_chars(&self.to_string()))
+    }
+}
+
+/// Serializes the pairs in their canonical form: keys and values encoded with
+/// `EncodeSet::Form` and every pair written as `key=value`.
+impl std::fmt::Display for QueryPairs {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, (k, v)) in self.pairs.iter().enumerate() {
+            if i > 0 {
+                fmt.write_char('&')?;
+            }
+            fmt.write_str(&percent_encode(k, EncodeSet::Form))?;
+            fmt.write_char('=')?;
+            fmt.write_str(&percent_encode(v, EncodeSet::Form))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a QueryPairs {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Box<dyn Iterator<Item = (&'a str, &'a str)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for QueryPairs {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> QueryPairs {
+        QueryPairs {
+            pairs: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a Query> for QueryPairs {
+    fn from(query: &'a Query) -> QueryPairs {
+        let mut pairs: Vec<(String, String)> = Vec::new();
+        for pair in query.0.split(|c| c.is(b'&')) {
+            if pair.is_empty() {
+                continue;
+            }
+            match pair.iter().position(|c| c.is(b'=')) {
+                Some(i) => pairs.push((decode(&pair[..i]), decode(&pair[i + 1..]))),
+                None => pairs.push((decode(pair), String::new())),
+            }
+        }
+        QueryPairs { pairs }
+    }
+}
+
+// Decodes a form-urlencoded key or value, replacing invalid UTF-8 like browsers do.
+fn decode(chars: &[Char]) -> String {
+    let bytes: Vec<u8> = chars
+        .iter()
+        .map(|c| match c.is(b'+') {
+            true => b' ',
+            false => c.byte(),
+        })
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+impl Query {
+    /// Returns the decoded form-urlencoded pairs of this query.
+    pub fn pairs(&self) -> QueryPairs {
+        QueryPairs::from(self)
+    }
+}
+
+impl Uri {
+    /// Returns the decoded form-urlencoded pairs of the query, which are empty if
+    /// there is no query.
+    pub fn query_pairs(&self) -> QueryPairs {
+        match &self.query {
+            Some(q) => q.pairs(),
+            None => QueryPairs::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common_failures::prelude::*;
+
+    #[test]
+    fn test_query_pairs() -> Result<()> {
+        let u: Uri = "http://a/?a=1&b=x+y%2Bz&a=2&&c&d=&%C3%A4=%E2%82%AC&e=%26%3D".parse()?;
+        let pairs = u.query_pairs();
+        assert_eq!(
+            vec![
+                ("a", "1"),
+                ("b", "x y+z"),
+                ("a", "2"),
+                ("c", ""),
+                ("d", ""),
+                ("ä", "€"),
+                ("e", "&="),
+            ],
+            pairs.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("1"), pairs.get("a"));
+        assert_eq!(vec!["1", "2"], pairs.get_all("a").collect::<Vec<_>>());
+        assert_eq!(Some(""), pairs.get("c"));
+        assert_eq!(None, pairs.get("x"));
+        assert!(pairs.contains_key("ä"));
+        assert_eq!(
+            "a=1&b=x+y%2Bz&a=2&c=&d=&%C3%A4=%E2%82%AC&e=%26%3D",
+            pairs.to_string()
+        );
+
+        let u: Uri = "http://a/".parse()?;
+        assert!(u.query_pairs().is_empty());
+
+        let u: Uri = "http://a/?x=%FF".parse()?;
+        assert_eq!(Some("\u{FFFD}"), u.query_pairs().get("x"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_query_pairs() -> Result<()> {
+        let mut pairs: QueryPairs = vec![("a", "1"), ("b", "2"), ("a", "3")]
+            .into_iter()
+            .collect();
+        pairs.set("a", "x y");
+        assert_eq!("a=x+y&b=2", pairs.to_string());
+        pairs.set("c", "&");
+        pairs.append("b", "4");
+        assert_eq!("a=x+y&b=2&c=%26&b=4", pairs.to_string());
+        pairs.remove("b");
+        assert_eq!("a=x+y&c=%26", pairs.to_string());
+        assert_eq!(2, pairs.len());
+
+        let q = pairs.to_query();
+        assert_eq!("a=x+y&c=%26", q.to_string());
+        assert_eq!(pairs, q.pairs());
+
+        let mut keys: Vec<&str> = Vec::new();
+        for (k, _) in &pairs {
+            keys.push(k);
+        }
+        assert_eq!(vec!["a", "c"], keys);
+        Ok(())
+    }
+}
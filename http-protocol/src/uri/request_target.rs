@@ -0,0 +1,354 @@
+//https://tools.ietf.org/html/rfc7230#section-5.3
+
+use uri::absolute_uri;
+use uri::authority;
+use uri::char_stream::Char;
+use uri::error;
+use uri::error::Production;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::last_production;
+use uri::optional_query;
+use uri::path_abempty;
+use uri::token_buffer::TokenBuffer;
+use uri::token_buffer::TokenStream;
+use uri::Authority;
+use uri::Uri;
+use Method;
+
+/// The request-target of an HTTP request line.
+///
+/// ```text
+/// request-target = origin-form / absolute-form / authority-form / asterisk-form
+/// origin-form    = absolute-path [ "?" query ]
+/// absolute-form  = absolute-URI
+/// authority-form = authority
+/// asterisk-form  = "*"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequestTarget {
+    /// A relative URI with an absolute path and an optional query, e.g. "/where?q=now".
+    Origin(Uri),
+    /// An absolute URI without fragment, as sent to proxies.
+    Absolute(Uri),
+    /// The host and port to connect to, for CONNECT.
+    Authority(Authority),
+    /// The server as a whole, for OPTIONS.
+    Asterisk,
+}
+
+impl RequestTarget {
+    /// Parses the request-target of a request with the given method. The method
+    /// decides between the otherwise ambiguous absolute-form and authority-form (is
+    /// "example.com:80" a URI with scheme "example.com"?), and the form has to be
+    /// allowed for the method.
+    pub fn parse(method: &Method, bytes: &[u8]) -> error::Result<RequestTarget> {
+        let target = match (method, bytes) {
+            (_, b"*") => Ok(RequestTarget::Asterisk),
+            (_, [b'/', ..]) => origin_form(bytes),
+            (Method::Connect, _) => authority_form(bytes),
+            _ => absolute_form(bytes),
+        };
+        let target = target.map_err(|e| e.with_input(bytes))?;
+        match target.is_allowed_for(method) {
+            true => Ok(target),
+            false => {
+                let byte = bytes.first().cloned();
+                let e = UriError::new(UriErrorKind::NotAllowed, target.form(), 0, byte);
+                Err(e.with_input(bytes))
+            }
+        }
+    }
+
+    /// Returns whether the form of this target may be used with the method: the
+    /// asterisk-form only with OPTIONS, the authority-form only with CONNECT, which
+    /// allows no other form.
+    pub fn is_allowed_for(&self, method: &Method) -> bool {
+        match (self, method) {
+            (RequestTarget::Asterisk, Method::Options) => true,
+            (RequestTarget::Asterisk, _) => false,
+            (RequestTarget::Authority(_), Method::Connect) => true,
+            (RequestTarget::Authority(_), _) => false,
+            (_, Method::Connect) => false,
+            _ => true,
+        }
+    }
+
+    fn form(&self) -> Production {
+        match self {
+            RequestTarget::Origin(_) => Production::OriginForm,
+            RequestTarget::Absolute(_) => Production::AbsoluteForm,
+            RequestTarget::Authority(_) => Production::AuthorityForm,
+            RequestTarget::Asterisk => Production::AsteriskForm,
+        }
+    }
+}
+
+impl std::fmt::Display for RequestTarget {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RequestTarget::Origin(u) => fmt.write_str(u.to_string().as_str()),
+            RequestTarget::Absolute(u) => fmt.write_str(u.to_string().as_str()),
+            RequestTarget::Authority(a) => fmt.write_str(a.to_string().as_str()),
+            RequestTarget::Asterisk => fmt.write_str("*"),
+        }
+    }
+}
+
+// origin-form = absolute-path [ "?" query ]
+// absolute-path = 1*( "/" segment )
+//
+// Unlike path-absolute of RFC 3986, the first segment may be empty, as in "//foo".
+// The bytes start with "/", so path-abempty parses exactly one or more segments.
+fn origin_form(bytes: &[u8]) -> error::Result<RequestTarget> {
+    let mut tb = TokenBuffer::from(bytes);
+    let path = path_abempty(&mut tb)?;
+    let u = Uri {
+        scheme: None,
+        authority: None,
+        path,
+        query: optional_query(&mut tb)?,
+        fragment: None,
+    };
+    complete(&mut tb, &u)?;
+    Ok(RequestTarget::Origin(u))
+}
+
+// absolute-form = absolute-URI
+fn absolute_form(bytes: &[u8]) -> error::Result<RequestTarget> {
+    let mut tb = TokenBuffer::from(bytes);
+    let u = match absolute_uri(&mut tb)? {
+        Some(u) => u,
+        None => return Err(UriError::unexpected(Production::Scheme, 0, tb.peek()?)),
+    };
+    complete(&mut tb, &u)?;
+    Ok(RequestTarget::Absolute(u))
+}
+
+// authority-form = uri-host ":" port (RFC 7231, section 4.3.6)
+fn authority_form(bytes: &[u8]) -> error::Result<RequestTarget> {
+    let mut tb = TokenBuffer::from(bytes);
+    let a = authority(&mut tb)?;
+    let offset = tb.offset();
+    if let Some(c) = tb.pop()? {
+        let production = match a.hostport.1 {
+            Some(_) => Production::Port,
+            None => Production::Host,
+        };
+        return Err(UriError::unexpected(production, offset, Some(c)));
+    }
+    if a.userinfo.is_some() {
+        let at = bytes.iter().position(|b| *b == b'@').unwrap();
+        return Err(UriError::unexpected(
+            Production::Host,
+            at,
+            Some(Char::Ascii(b'@')),
+        ));
+    }
+    match &a.hostport.1 {
+        Some(p) if !p.0.is_empty() => Ok(RequestTarget::Authority(a)),
+        _ => Err(UriError::unexpected(Production::Port, offset, None)),
+    }
+}
+
+fn complete<T>(tb: &mut TokenBuffer<Char, T>, u: &Uri) -> error::Result<()>
+where
+    T: TokenStream<Char>,
+{
+    let offset = tb.offset();
+    match tb.pop()? {
+        Some(c) => Err(UriError::unexpected(last_production(u), offset, Some(c))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::error::Result;
+
+    fn parse(method: &str, target: &str) -> Result<RequestTarget> {
+        RequestTarget::parse(&Method::from(method.to_string()), target.as_bytes())
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        match parse("GET", "/where?q=now")? {
+            RequestTarget::Origin(u) => {
+                assert_eq!("/where", u.path().to_string());
+                assert_eq!(Some("now"), u.query_pairs().get("q"));
+            }
+            t => panic!("Unexpected target {:?}", t),
+        }
+        assert_eq!("/", parse("POST", "/")?.to_string());
+        for target in &["//foo", "//", "//a//b?c"] {
+            match parse("GET", target)? {
+                RequestTarget::Origin(u) => {
+                    assert_eq!(None, u.authority());
+                    assert_eq!(*target, u.to_string());
+                }
+                t => panic!("Unexpected target {:?}", t),
+            }
+        }
+
+        match parse("GET", "http://www.example.org/pub/WWW/TheProject.html")? {
+            RequestTarget::Absolute(u) => {
+                assert_eq!(
+                    Some("www.example.org".to_string()),
+                    u.host().map(|h| h.to_string())
+                );
+            }
+            t => panic!("Unexpected target {:?}", t),
+        }
+
+        match parse("CONNECT", "www.example.com:80")? {
+            RequestTarget::Authority(a) => {
+                assert_eq!("www.example.com:80", a.to_string());
+            }
+            t => panic!("Unexpected target {:?}", t),
+        }
+        assert_eq!("[::1]:443", parse("CONNECT", "[::1]:443")?.to_string());
+
+        assert_eq!(RequestTarget::Asterisk, parse("OPTIONS", "*")?);
+        assert_eq!(
+            RequestTarget::Absolute("http://a/".parse()?),
+            parse("OPTIONS", "http://a/")?
+        );
+        assert_eq!(
+            RequestTarget::Origin("/x".parse()?),
+            parse("PROPFIND", "/x")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = [
+            (
+                "GET",
+                "/a#frag",
+                UriErrorKind::UnexpectedByte,
+                Production::Path,
+                2,
+            ),
+            (
+                "GET",
+                "//a b",
+                UriErrorKind::UnexpectedByte,
+                Production::Path,
+                3,
+            ),
+            (
+                "GET",
+                "/a b",
+                UriErrorKind::UnexpectedByte,
+                Production::Path,
+                2,
+            ),
+            (
+                "GET",
+                "www.example.com/",
+                UriErrorKind::UnexpectedByte,
+                Production::Scheme,
+                0,
+            ),
+            (
+                "GET",
+                "http://a/#frag",
+                UriErrorKind::UnexpectedByte,
+                Production::Path,
+                9,
+            ),
+            (
+                "GET",
+                "",
+                UriErrorKind::UnexpectedEnd,
+                Production::Scheme,
+                0,
+            ),
+            (
+                "CONNECT",
+                "www.example.com",
+                UriErrorKind::UnexpectedEnd,
+                Production::Port,
+                15,
+            ),
+            (
+                "CONNECT",
+                "www.example.com:",
+                UriErrorKind::UnexpectedEnd,
+                Production::Port,
+                16,
+            ),
+            (
+                "CONNECT",
+                "user@www.example.com:80",
+                UriErrorKind::UnexpectedByte,
+                Production::Host,
+                4,
+            ),
+            (
+                "CONNECT",
+                "www.example.com:80/",
+                UriErrorKind::UnexpectedByte,
+                Production::Port,
+                18,
+            ),
+            (
+                "CONNECT",
+                "www.example.com:65536",
+                UriErrorKind::Invalid,
+                Production::Port,
+                16,
+            ),
+            (
+                "GET",
+                "*",
+                UriErrorKind::NotAllowed,
+                Production::AsteriskForm,
+                0,
+            ),
+            (
+                "CONNECT",
+                "/",
+                UriErrorKind::NotAllowed,
+                Production::OriginForm,
+                0,
+            ),
+        ];
+        for (method, target, kind, production, offset) in errors.iter() {
+            let e = parse(method, target).unwrap_err();
+            assert_eq!(*kind, e.kind(), "{} {}", method, target);
+            assert_eq!(*production, e.production(), "{} {}", method, target);
+            assert_eq!(*offset, e.offset(), "{} {}", method, target);
+            assert_eq!(Some(target.as_bytes()), e.input(), "{} {}", method, target);
+        }
+
+        assert_eq!(
+            "Unexpected character '#' in path at byte 2.\n/a#frag\n  ^",
+            parse("GET", "/a#frag").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "The asterisk-form at byte 0 is not allowed.\n*\n^",
+            parse("GET", "*").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "The origin-form at byte 0 is not allowed.\n/\n^",
+            parse("CONNECT", "/").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_is_allowed_for() -> Result<()> {
+        let origin = RequestTarget::Origin("/".parse()?);
+        let authority = parse("CONNECT", "a:1")?;
+        assert!(origin.is_allowed_for(&Method::Get));
+        assert!(!origin.is_allowed_for(&Method::Connect));
+        assert!(authority.is_allowed_for(&Method::Connect));
+        assert!(!authority.is_allowed_for(&Method::Options));
+        assert!(RequestTarget::Asterisk.is_allowed_for(&Method::Options));
+        assert!(!RequestTarget::Asterisk.is_allowed_for(&Method::Head));
+        Ok(())
+    }
+}
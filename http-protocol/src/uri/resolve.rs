@@ -0,0 +1,399 @@
+//https://tools.ietf.org/html/rfc3986#section-5.2
+
+use uri::char_stream::Char;
+use uri::path_empty;
+use uri::PathSegments;
+use uri::Segment;
+use uri::Uri;
+
+impl Uri {
+    /// Resolves `reference` against this URI as its base, following the strict
+    /// "Transform References" algorithm of RFC 3986.
+    pub fn resolve(&self, reference: &Uri) -> Uri {
+        let r = reference;
+        let mut t = r.clone();
+        if r.scheme.is_some() {
+            t.path = remove_dot_segments(&r.path);
+        } else {
+            if r.authority.is_some() {
+                t.path = remove_dot_segments(&r.path);
+            } else {
+                if r.path.is_empty() {
+                    t.path = self.path.clone();
+                    if r.query.is_none() {
+                        t.query = self.query.clone();
+                    }
+                } else if r.path.is_absolute() {
+                    t.path = remove_dot_segments(&r.path);
+                } else {
+                    t.path = remove_dot_segments(&merge(self, &r.path));
+                }
+                t.authority = self.authority.clone();
+            }
+            t.scheme = self.scheme.clone();
+        }
+        t
+    }
+
+    /// Computes the shortest reference that resolves against this URI as its
+    /// base to `target`, the inverse of `resolve`. Returns `None` if this URI
+    /// is not absolute, the schemes differ, or no reference resolves to
+    /// `target` (e.g. because its path contains dot segments).
+    pub fn make_relative(&self, target: &Uri) -> Option<Uri> {
+        if self.scheme.is_none() || self.scheme != target.scheme {
+            return None;
+        }
+        let mut r = Uri {
+            scheme: None,
+            authority: None,
+            path: path_empty(),
+            query: target.query.clone(),
+            fragment: target.fragment.clone(),
+        };
+        if self.authority != target.authority {
+            r.authority = target.authority.clone();
+            r.path = target.path.clone();
+        } else if self.path == target.path && (self.query == target.query || target.query.is_some())
+        {
+            if self.query == target.query {
+                r.query = None;
+            }
+        } else {
+            r.path = relative_path(self, &target.path);
+        }
+
+        if self.resolve(&r) == *target {
+            return Some(r);
+        }
+        if target.authority.is_some() {
+            r.authority = target.authority.clone();
+            r.path = target.path.clone();
+            r.query = target.query.clone();
+            if self.resolve(&r) == *target {
+                return Some(r);
+            }
+        }
+        if self.resolve(target) == *target {
+            return Some(target.clone());
+        }
+        None
+    }
+}
+
+// The shortest relative-path or absolute-path reference from the directory of
+// the base to the given path.
+fn relative_path(base: &Uri, path: &PathSegments) -> PathSegments {
+    let root = [Segment::new()];
+    let directory: &[Segment] = if base.authority.is_some() && base.path.is_empty() {
+        &root
+    } else {
+        &base.path.segments[..base.path.segments.len() - 1]
+    };
+    let target_directory = &path.segments[..path.segments.len() - 1];
+
+    let mut common = 0;
+    while common < directory.len()
+        && common < target_directory.len()
+        && directory[common] == target_directory[common]
+    {
+        common += 1;
+    }
+
+    let mut segments: Vec<Segment> = Vec::new();
+    for _ in common..directory.len() {
+        segments.push(Segment::dot_dot());
+    }
+    let rest = &path.segments[common..];
+    if rest.len() == 1 && rest[0].is_empty() {
+        // The target is a directory: "." or ".." already resolve to it.
+        if segments.is_empty() {
+            segments.push(Segment::dot());
+        }
+    } else {
+        // "./" keeps a leading empty segment or a colon in the first segment from
+        // changing the meaning of the reference.
+        if segments.is_empty() && (rest[0].is_empty() || rest[0].to_string().contains(':')) {
+            segments.push(Segment::dot());
+        }
+        segments.extend(rest.iter().cloned());
+    }
+    let relative = PathSegments { segments };
+
+    // An absolute path is shorter when many ".." would be needed, but a path
+    // starting with "//" would be taken for an authority.
+    let network_path = path.segments.len() > 2 && path.segments[1].is_empty();
+    if path.is_absolute() && !network_path && path.to_string().len() < relative.to_string().len() {
+        return path.clone();
+    }
+    relative
+}
+
+fn merge(base: &Uri, path: &PathSegments) -> PathSegments {
+    let mut segments: Vec<Segment>;
+    if base.authority.is_some() && base.path.is_empty() {
+        segments = vec![Segment::new()];
+    } else {
+        segments = base.path.segments.clone();
+        segments.pop();
+    }
+    segments.extend(path.segments.iter().cloned());
+    PathSegments { segments }
+}
+
+// A segment of the input or output buffer of remove_dot_segments, together with
+// whether it is preceded by a "/".
+struct Unit(bool, Segment);
+
+pub(crate) fn remove_dot_segments(path: &PathSegments) -> PathSegments {
+    let mut input: Vec<Unit> = Vec::new();
+    for (i, s) in path.segments.iter().enumerate() {
+        if i > 0 || !s.is_empty() {
+            input.push(Unit(i > 0, s.clone()));
+        }
+    }
+    input.reverse();
+
+    let mut output: Vec<Unit> = Vec::new();
+    while let Some(unit) = input.pop() {
+        let is_last = input.is_empty();
+        match unit {
+            // A: "../" or "./" prefix, D: "." or ".." as the whole input
+            Unit(false, ref s) if s.is_dot() || s.is_dot_dot() => {
+                if let Some(next) = input.pop() {
+                    input.push(Unit(false, next.1));
+                }
+            }
+            // B: "/./" or "/." prefix
+            Unit(true, ref s) if s.is_dot() => {
+                if is_last {
+                    input.push(Unit(true, Segment::new()));
+                }
+            }
+            // C: "/../" or "/.." prefix
+            Unit(true, ref s) if s.is_dot_dot() => {
+                output.pop();
+                if is_last {
+                    input.push(Unit(true, Segment::new()));
+                }
+            }
+            // E: move the first path segment to the output
+            unit => output.push(unit),
+        }
+    }
+
+    let mut segments: Vec<Segment> = Vec::new();
+    if let Some(Unit(true, _)) = output.first() {
+        segments.push(Segment::new());
+    }
+    segments.extend(output.into_iter().map(|u| u.1));
+    if segments.is_empty() {
+        segments.push(Segment::new());
+    }
+    PathSegments { segments }
+}
+
+impl Segment {
+    fn dot() -> Segment {
+        Segment {
+            pchars: vec![Char::Ascii(b'.')],
+            params: None,
+        }
+    }
+
+    fn dot_dot() -> Segment {
+        Segment {
+            pchars: vec![Char::Ascii(b'.'), Char::Ascii(b'.')],
+            params: None,
+        }
+    }
+
+    fn is_dot(&self) -> bool {
+        self.params.is_none() && self.pchars == [Char::Ascii(b'.')]
+    }
+
+    fn is_dot_dot(&self) -> bool {
+        self.params.is_none() && self.pchars == [Char::Ascii(b'.'), Char::Ascii(b'.')]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common_failures::prelude::*;
+
+    fn resolve(base: &str, reference: &str) -> Result<String> {
+        let base: Uri = base.parse()?;
+        let reference: Uri = reference.parse()?;
+        Ok(base.resolve(&reference).to_string())
+    }
+
+    #[test]
+    fn test_resolve_normal_examples() -> Result<()> {
+        // https://tools.ietf.org/html/rfc3986#section-5.4.1
+        let base = "http://a/b/c/d;p?q";
+        let examples = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+        ];
+        for (reference, target) in examples.iter() {
+            assert_eq!(*target, resolve(base, reference)?, "{}", reference);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_abnormal_examples() -> Result<()> {
+        // https://tools.ietf.org/html/rfc3986#section-5.4.2
+        let base = "http://a/b/c/d;p?q";
+        let examples = [
+            ("../../../g", "http://a/g"),
+            ("../../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            ("g?y/./x", "http://a/b/c/g?y/./x"),
+            ("g?y/../x", "http://a/b/c/g?y/../x"),
+            ("g#s/./x", "http://a/b/c/g#s/./x"),
+            ("g#s/../x", "http://a/b/c/g#s/../x"),
+            ("http:g", "http:g"),
+        ];
+        for (reference, target) in examples.iter() {
+            assert_eq!(*target, resolve(base, reference)?, "{}", reference);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_empty_base_path() -> Result<()> {
+        assert_eq!("http://a/g", resolve("http://a", "g")?);
+        assert_eq!("http://a?q", resolve("http://a", "?q")?);
+        assert_eq!("foo:b", resolve("foo:a", "b")?);
+        assert_eq!("foo:a/c", resolve("foo:a/b", "c")?);
+        Ok(())
+    }
+
+    fn make_relative(base: &str, target: &str) -> Result<Option<String>> {
+        let base: Uri = base.parse()?;
+        let target: Uri = target.parse()?;
+        let r = base.make_relative(&target);
+        if let Some(r) = &r {
+            assert_eq!(target, base.resolve(r), "{} {}", target, r);
+        }
+        Ok(r.map(|r| r.to_string()))
+    }
+
+    #[test]
+    fn test_make_relative() -> Result<()> {
+        let base = "http://a/b/c/d;p?q";
+        let examples = [
+            ("http://a/b/x/y", "../x/y"),
+            ("http://a/b/c/g", "g"),
+            ("http://a/b/c/g/", "g/"),
+            ("http://a/b/c/", "."),
+            ("http://a/b/", ".."),
+            ("http://a/", "/"),
+            ("http://a/g", "/g"),
+            ("http://a/b/g", "../g"),
+            ("http://a/b/c/d;p?q", ""),
+            ("http://a/b/c/d;p?q#s", "#s"),
+            ("http://a/b/c/d;p?y", "?y"),
+            ("http://a/b/c/d;p", "d;p"),
+            ("http://a/b/c/g:h", "./g:h"),
+            ("http://a/b/c//g", ".//g"),
+            ("http://a//g", "../..//g"),
+            ("http://g/x", "//g/x"),
+            ("http://a", "//a"),
+            ("http://a/b/c/g?y#s", "g?y#s"),
+        ];
+        for (target, reference) in examples.iter() {
+            assert_eq!(
+                Some(reference.to_string()),
+                make_relative(base, target)?,
+                "{}",
+                target
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_relative_edge_cases() -> Result<()> {
+        assert_eq!(None, make_relative("http://a/b", "https://a/b")?);
+        assert_eq!(None, make_relative("b/c", "b/d")?);
+        assert_eq!(
+            Some("g".to_string()),
+            make_relative("http://a", "http://a/g")?
+        );
+        assert_eq!(
+            Some("b".to_string()),
+            make_relative("mailto:a", "mailto:b")?
+        );
+        assert_eq!(Some("c".to_string()), make_relative("foo:a/b", "foo:a/c")?);
+        assert_eq!(
+            Some("foo:b".to_string()),
+            make_relative("foo:a/b", "foo:b")?
+        );
+        assert_eq!(None, make_relative("http://a/b", "http:/a/../b")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_dot_segments() -> Result<()> {
+        // https://tools.ietf.org/html/rfc3986#section-5.2.4
+        let examples = [
+            ("/a/b/c/./../../g", "/a/g"),
+            ("mid/content=5/../6", "mid/6"),
+            ("", ""),
+            ("/", "/"),
+            (".", ""),
+            ("..", ""),
+            ("../a", "a"),
+            ("./a/..", "/"),
+            ("a/../b", "/b"),
+            ("/a/..", "/"),
+            ("/a/.", "/a/"),
+            ("/a//../b", "/a/b"),
+        ];
+        for (path, target) in examples.iter() {
+            let u: Uri = path.parse()?;
+            assert_eq!(
+                *target,
+                remove_dot_segments(u.path()).to_string(),
+                "{}",
+                path
+            );
+        }
+        Ok(())
+    }
+}
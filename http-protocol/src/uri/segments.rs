@@ -0,0 +1,220 @@
+use uri::builder::check_path;
+use uri::builder::encode_segment;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::PathSegments;
+use uri::Segment;
+use uri::Uri;
+
+/// A path segment with its escapes decoded, split into the name before the first
+/// ";" and the `key=value` matrix parameters after it. Invalid UTF-8 is replaced
+/// like browsers do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedSegment {
+    name: String,
+    params: Vec<(String, String)>,
+}
+
+impl DecodedSegment {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the parameters in order. A parameter without "=" has an empty value.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the value of the first parameter with the given key.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl<'a> From<&'a Segment> for DecodedSegment {
+    fn from(segment: &'a Segment) -> DecodedSegment {
+        let mut params: Vec<(String, String)> = Vec::new();
+        if let Some(ps) = &segment.params {
+            for p in ps {
+                let decoded = String::from_utf8_lossy(&p.decode()).into_owned();
+                match decoded.find('=') {
+                    Some(i) => {
+                        params.push((decoded[..i].to_string(), decoded[i + 1..].to_string()))
+                    }
+                    None => params.push((decoded, String::new())),
+                }
+            }
+        }
+        DecodedSegment {
+            name: String::from_utf8_lossy(&segment.decode()).into_owned(),
+            params,
+        }
+    }
+}
+
+impl PathSegments {
+    /// Returns the segments after the leading "/" of an absolute path, so "/a/b/"
+    /// has the segments "a", "b" and "".
+    pub fn iter(&self) -> impl Iterator<Item = &Segment> {
+        let root = self.is_absolute() as usize;
+        self.segments[root..].iter()
+    }
+
+    /// Returns the decoded segments, like `iter`.
+    pub fn decoded(&self) -> impl Iterator<Item = DecodedSegment> + '_ {
+        self.iter().map(DecodedSegment::from)
+    }
+
+    /// Returns whether the path ends with "/", i.e. names a directory.
+    pub fn is_directory(&self) -> bool {
+        self.segments.len() > 1 && self.segments[self.segments.len() - 1].is_empty()
+    }
+}
+
+impl Uri {
+    /// Returns the decoded segments of the path, see `PathSegments::iter`.
+    pub fn path_segments(&self) -> impl Iterator<Item = DecodedSegment> + '_ {
+        self.path.decoded()
+    }
+
+    /// Appends a percent-encoded segment to the path, replacing a trailing empty
+    /// segment. An empty path becomes absolute, so "http://a" becomes "http://a/b".
+    pub fn push_segment(&mut self, s: &str) -> Result<()> {
+        let mut path = self.path.clone();
+        if path.segments.len() > 1 && path.segments[path.segments.len() - 1].is_empty() {
+            path.segments.pop();
+        } else if path.is_empty() {
+            path.segments = vec![Segment::new()];
+        }
+        path.segments.push(encode_segment(s)?);
+        self.set_path_segments(path)
+    }
+
+    /// Removes the last segment of the path and returns it, keeping the leading "/"
+    /// of an absolute path. Returns `None` if the path is "/" or empty.
+    pub fn pop_segment(&mut self) -> Option<DecodedSegment> {
+        let root = self.path.is_absolute() as usize;
+        let segments = &mut self.path.segments;
+        if segments.len() <= root + 1 && segments[segments.len() - 1].is_empty() {
+            return None;
+        }
+        let s = segments.pop();
+        if segments.len() == root {
+            segments.push(Segment::new());
+        }
+        s.as_ref().map(DecodedSegment::from)
+    }
+
+    /// Replaces the segment at `index`, counted like `PathSegments::iter`, with a
+    /// percent-encoded segment. An index past the last segment is not allowed at the
+    /// end of the path.
+    pub fn replace_segment(&mut self, index: usize, s: &str) -> Result<()> {
+        let mut path = self.path.clone();
+        let i = index + path.is_absolute() as usize;
+        if i >= path.segments.len() {
+            let input = path.to_string();
+            let e = UriError::new(
+                UriErrorKind::NotAllowed,
+                Production::Path,
+                input.len(),
+                None,
+            );
+            return Err(e.with_input(input.as_bytes()));
+        }
+        path.segments[i] = encode_segment(s)?;
+        self.set_path_segments(path)
+    }
+
+    fn set_path_segments(&mut self, path: PathSegments) -> Result<()> {
+        check_path(&path, self.scheme.is_some(), self.authority.is_some())
+            .map_err(|e| e.with_input(path.to_string().as_bytes()))?;
+        self.path = path;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_segments() -> Result<()> {
+        let u: Uri = "http://a/api;v=2/users/j%C3%BCrgen;lang=de;x/".parse()?;
+        let segments: Vec<DecodedSegment> = u.path_segments().collect();
+        assert_eq!(4, segments.len());
+        assert_eq!("api", segments[0].name());
+        assert_eq!(Some("2"), segments[0].param("v"));
+        assert_eq!("users", segments[1].name());
+        assert_eq!("jürgen", segments[2].name());
+        assert_eq!(
+            vec![("lang", "de"), ("x", "")],
+            segments[2].params().collect::<Vec<_>>()
+        );
+        assert_eq!(None, segments[2].param("v"));
+        assert_eq!("", segments[3].name());
+        assert!(u.path().is_directory());
+
+        let u: Uri = "a/b%2Fc".parse()?;
+        let names: Vec<String> = u.path_segments().map(|s| s.name().to_string()).collect();
+        assert_eq!(vec!["a", "b/c"], names);
+        assert!(!u.path().is_directory());
+
+        let u: Uri = "http://a".parse()?;
+        assert_eq!(1, u.path_segments().count());
+        assert!(!u.path().is_directory());
+
+        let u: Uri = "http://a/".parse()?;
+        assert!(u.path().is_directory());
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_segments() -> Result<()> {
+        let mut u: Uri = "http://a/b/?q#f".parse()?;
+        u.push_segment("c d")?;
+        u.push_segment("e/f;g")?;
+        assert_eq!("http://a/b/c%20d/e%2Ff%3Bg?q#f", u.to_string());
+
+        assert_eq!("e/f;g", u.pop_segment().unwrap().name());
+        u.replace_segment(0, "x")?;
+        assert_eq!("http://a/x/c%20d?q#f", u.to_string());
+        let e = u.replace_segment(2, "y").unwrap_err();
+        assert_eq!(UriErrorKind::NotAllowed, e.kind());
+        assert_eq!(Production::Path, e.production());
+        assert_eq!(8, e.offset());
+        assert_eq!(
+            "The path at byte 8 is not allowed.\n/x/c%20d\n        ^",
+            e.to_string()
+        );
+
+        assert_eq!("c d", u.pop_segment().unwrap().name());
+        assert_eq!("x", u.pop_segment().unwrap().name());
+        assert_eq!("http://a/?q#f", u.to_string());
+        assert_eq!(None, u.pop_segment());
+
+        let mut u: Uri = "http://a".parse()?;
+        u.push_segment("b")?;
+        assert_eq!("http://a/b", u.to_string());
+
+        let mut u: Uri = "a/b/".parse()?;
+        assert_eq!("", u.pop_segment().unwrap().name());
+        assert_eq!("b", u.pop_segment().unwrap().name());
+        assert_eq!("a", u.pop_segment().unwrap().name());
+        assert_eq!("", u.to_string());
+        assert_eq!(None, u.pop_segment());
+
+        let mut u: Uri = "/a/b".parse()?;
+        assert_eq!(1, u.replace_segment(0, "").unwrap_err().offset());
+        let mut u: Uri = "a".parse()?;
+        let e = u.replace_segment(0, "x:y").unwrap_err();
+        assert_eq!((Production::Path, 1), (e.production(), e.offset()));
+        assert_eq!(Some(&b"x:y"[..]), e.input());
+        assert_eq!("a", u.to_string());
+        Ok(())
+    }
+}
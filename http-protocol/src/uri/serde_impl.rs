@@ -0,0 +1,266 @@
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use std::fmt::Formatter;
+use uri::builder::complete;
+use uri::error::Production;
+use uri::error::UriError;
+use uri::host;
+use uri::percent::encoded_chars;
+use uri::scheme;
+use uri::Host;
+use uri::Scheme;
+use uri::Uri;
+
+// URIs, schemes and hosts are serialized as strings and deserialized through the
+// parsers of RFC 3986, so a config file with an invalid URI fails to load with the
+// position of the offending character.
+
+impl Serialize for Uri {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Uri {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Uri, D::Error> {
+        deserializer.deserialize_str(UriVisitor)
+    }
+}
+
+struct UriVisitor;
+
+impl<'de> de::Visitor<'de> for UriVisitor {
+    type Value = Uri;
+
+    fn expecting(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.write_str("a URI string")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Uri, E> {
+        self.visit_bytes(s.as_bytes())
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Uri, E> {
+        Uri::parse(bytes).map_err(E::custom)
+    }
+}
+
+impl Serialize for Scheme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scheme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Scheme, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let chars = encoded_chars(&s);
+        let first = chars.first().cloned();
+        match complete(chars, Production::Scheme, scheme) {
+            Ok(Some(scheme)) => Ok(scheme),
+            Ok(None) => Err(de::Error::custom(UriError::unexpected(
+                Production::Scheme,
+                0,
+                first,
+            ))),
+            Err(e) => Err(de::Error::custom(e)),
+        }
+    }
+}
+
+impl Serialize for Host {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Host {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Host, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        complete(encoded_chars(&s), Production::Host, host).map_err(de::Error::custom)
+    }
+}
+
+/// Serializes a `Uri` as a map of its percent-encoded components instead of a
+/// string, for use with `#[serde(with = "http_protocol::uri::structured")]`:
+///
+/// ```text
+/// {"scheme": "http", "host": "example.com", "port": 8080, "path": "/a%20b",
+///  "query": "q", "fragment": null}
+/// ```
+///
+/// The "userinfo" is only written if there is one. An empty port, as in
+/// "http://example.com:/", is dropped.
+pub mod structured {
+    use super::*;
+
+    #[derive(PartialEq, Serialize, Deserialize)]
+    struct Components {
+        #[serde(default)]
+        scheme: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        userinfo: Option<String>,
+        #[serde(default)]
+        host: Option<String>,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        path: String,
+        #[serde(default)]
+        query: Option<String>,
+        #[serde(default)]
+        fragment: Option<String>,
+    }
+
+    impl<'a> From<&'a Uri> for Components {
+        fn from(uri: &'a Uri) -> Components {
+            Components {
+                scheme: uri.scheme.as_ref().map(|s| s.to_string()),
+                userinfo: uri.userinfo().map(|u| u.to_string()),
+                host: uri.host().map(|h| h.to_string()),
+                port: uri.port(),
+                path: uri.path.to_string(),
+                query: uri.query.as_ref().map(|q| q.to_string()),
+                fragment: uri.fragment.as_ref().map(|f| f.to_string()),
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(uri: &Uri, serializer: S) -> Result<S::Ok, S::Error> {
+        Components::from(uri).serialize(serializer)
+    }
+
+    /// Deserializes the components by parsing the URI they make up, so they have to
+    /// be percent-encoded.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uri, D::Error> {
+        let c = Components::deserialize(deserializer)?;
+        let mut s = String::new();
+        if let Some(scheme) = &c.scheme {
+            s.push_str(scheme);
+            s.push(':');
+        }
+        match &c.host {
+            Some(host) => {
+                s.push_str("//");
+                if let Some(userinfo) = &c.userinfo {
+                    s.push_str(userinfo);
+                    s.push('@');
+                }
+                s.push_str(host);
+                if let Some(port) = c.port {
+                    s.push_str(&format!(":{}", port));
+                }
+            }
+            None if c.userinfo.is_some() || c.port.is_some() => {
+                return Err(de::Error::custom(
+                    "A URI without a host has no userinfo or port.",
+                ))
+            }
+            None => {}
+        }
+        s.push_str(&c.path);
+        if let Some(query) = &c.query {
+            s.push('?');
+            s.push_str(query);
+        }
+        if let Some(fragment) = &c.fragment {
+            s.push('#');
+            s.push_str(fragment);
+        }
+        let uri = Uri::parse(s.as_bytes()).map_err(de::Error::custom)?;
+        // Components that run into each other, like the host "a" and the path "b",
+        // still make up a valid URI, but not one with these components.
+        match Components::from(&uri) == c {
+            true => Ok(uri),
+            false => Err(de::Error::custom(format!(
+                "The components do not make up the URI '{}'.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common_failures::prelude::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Endpoint {
+        uri: Uri,
+        scheme: Scheme,
+        host: Host,
+        #[serde(with = "structured")]
+        structured: Uri,
+    }
+
+    #[test]
+    fn test_string_form() -> Result<()> {
+        let u: Uri = "http://user@example.com:8080/a%20b?q#f".parse()?;
+        let json = serde_json::to_string(&u)?;
+        assert_eq!("\"http://user@example.com:8080/a%20b?q#f\"", json);
+        assert_eq!(u, serde_json::from_str::<Uri>(&json)?);
+
+        let e = serde_json::from_str::<Uri>("\"http://exa mple.com\"").unwrap_err();
+        assert_eq!(
+            "Unexpected character ' ' in host at byte 10.\nhttp://exa mple.com\n          ^ at line 1 column 21",
+            e.to_string()
+        );
+        assert!(serde_json::from_str::<Uri>("42").is_err());
+
+        let s: Scheme = serde_json::from_str("\"HTTPS\"")?;
+        assert_eq!("HTTPS", s.to_string());
+        assert!(serde_json::from_str::<Scheme>("\"1http\"").is_err());
+        assert!(serde_json::from_str::<Scheme>("\"ht tp\"").is_err());
+
+        let h: Host = serde_json::from_str("\"[::1]\"")?;
+        assert!(h.ip_addr().is_some());
+        assert!(serde_json::from_str::<Host>("\"a/b\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_structured_form() -> Result<()> {
+        let json = r#"{
+            "uri": "mailto:a@b",
+            "scheme": "ftp",
+            "host": "example.com",
+            "structured": {"scheme": "http", "userinfo": "u:p", "host": "example.com",
+                           "port": 8080, "path": "/a%20b", "query": "", "fragment": "f"}
+        }"#;
+        let e: Endpoint = serde_json::from_str(json)?;
+        assert_eq!(
+            "http://u:p@example.com:8080/a%20b?#f",
+            e.structured.to_string()
+        );
+        assert_eq!(e, serde_json::from_str(&serde_json::to_string(&e)?)?);
+
+        let u: Uri = "//a".parse()?;
+        assert_eq!(
+            r#"{"scheme":null,"host":"a","port":null,"path":"","query":null,"fragment":null}"#,
+            serde_json::to_string(&Structured(u))?
+        );
+
+        let u: Uri = serde_json::from_str::<Structured>(r#"{"path": "a/b"}"#)?.0;
+        assert_eq!("a/b", u.to_string());
+
+        let errors = [
+            r#"{"path": "a b"}"#,
+            r#"{"port": 80, "path": "/"}"#,
+            r#"{"host": "a", "path": "b"}"#,
+            r#"{"scheme": "http", "host": "a", "path": "/?x"}"#,
+            r##"{"scheme": "http", "host": "a", "path": "/", "query": "#x"}"##,
+        ];
+        for e in errors.iter() {
+            assert!(serde_json::from_str::<Structured>(e).is_err(), "{}", e);
+        }
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Structured(#[serde(with = "structured")] Uri);
+}
@@ -0,0 +1,335 @@
+//https://tools.ietf.org/html/rfc3966
+
+use std::str::FromStr;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::percent_decode_str;
+use uri::Uri;
+
+/// A "tel:" URI with a global number, like "tel:+1-201-555-0123", or a local number
+/// that needs a "phone-context", like "tel:7042;phone-context=example.com".
+///
+/// ```text
+/// telephone-subscriber = global-number / local-number
+/// global-number        = global-number-digits *par
+/// local-number         = local-number-digits *par context *par
+/// par                  = parameter / extension / isdn-subaddress
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TelUri {
+    number: String,
+    params: Vec<(String, Option<String>)>,
+}
+
+impl TelUri {
+    /// Returns the view of a URI with the scheme "tel". Error offsets are the ones in
+    /// the URI.
+    pub fn from_uri(uri: &Uri) -> Result<TelUri> {
+        let (opaque_part, offset) = uri.opaque_part_for("tel")?;
+        parse_opaque_part(&opaque_part)
+            .map_err(|e| e.shifted(offset).with_input(uri.to_string().as_bytes()))
+    }
+
+    /// Returns whether the number starts with "+" and is unique worldwide.
+    pub fn is_global(&self) -> bool {
+        self.number.starts_with('+')
+    }
+
+    /// Returns the number as written, including visual separators.
+    pub fn number(&self) -> &str {
+        &self.number
+    }
+
+    /// Returns the number without visual separators, e.g. "+12015550123" for
+    /// "+1-201-555-0123".
+    pub fn digits(&self) -> String {
+        self.number
+            .chars()
+            .filter(|c| !is_visual_separator(*c))
+            .collect()
+    }
+
+    /// Returns the decoded parameters in order, with lowercase names.
+    pub fn params(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.params.iter().map(|(n, v)| (n.as_str(), v.as_deref()))
+    }
+
+    /// Returns the value of the parameter with the given name, which is compared
+    /// case-insensitively.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        self.param("ext")
+    }
+
+    pub fn isdn_subaddress(&self) -> Option<&str> {
+        self.param("isub")
+    }
+
+    /// Returns the domain name or global number prefix a local number is valid in.
+    pub fn phone_context(&self) -> Option<&str> {
+        self.param("phone-context")
+    }
+}
+
+impl FromStr for TelUri {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<TelUri> {
+        TelUri::from_uri(&Uri::parse(s.as_bytes())?)
+    }
+}
+
+// Parses what follows "tel:". Error offsets are the ones in it, those of errors in a
+// parameter the one of the parameter.
+fn parse_opaque_part(opaque_part: &str) -> Result<TelUri> {
+    let byte = |i: usize| opaque_part.as_bytes().get(i).cloned();
+    let invalid = |production, i| UriError::invalid(production, i, byte(i).map(Char::Ascii));
+    let not_allowed =
+        |production, i| UriError::new(UriErrorKind::NotAllowed, production, i, byte(i));
+
+    let mut parts = opaque_part.split(';');
+    let number = parts.next().unwrap_or("");
+    let mut start = number.len() + 1;
+    let number = percent_decode_str(number)?.into_owned();
+
+    let mut params: Vec<(String, Option<String>)> = Vec::new();
+    let mut offsets: Vec<usize> = Vec::new();
+    for p in parts {
+        let (name, value) = match p.find('=') {
+            Some(i) => {
+                let value =
+                    percent_decode_str(&p[i + 1..]).map_err(|e| e.shifted(start + i + 1))?;
+                (&p[..i], Some(value.into_owned()))
+            }
+            None => (p, None),
+        };
+        let name = name.to_ascii_lowercase();
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+            return Err(invalid(Production::Parameter, start));
+        }
+        if params.iter().any(|(n, _)| *n == name) {
+            return Err(not_allowed(Production::Parameter, start));
+        }
+        params.push((name, value));
+        offsets.push(start);
+        start += p.len() + 1;
+    }
+    let tel = TelUri { number, params };
+    let offset_of = |name: &str| offsets[tel.params.iter().position(|(n, _)| n == name).unwrap()];
+
+    let valid_number = match tel.is_global() {
+        true => is_global_number_digits(&tel.number),
+        false => is_local_number_digits(&tel.number),
+    };
+    if !valid_number {
+        return Err(invalid(Production::TelephoneNumber, 0));
+    }
+    let valid_context = match (tel.is_global(), tel.phone_context()) {
+        (false, None) => return Err(not_allowed(Production::TelephoneNumber, 0)),
+        (true, Some(_)) => {
+            return Err(not_allowed(
+                Production::Parameter,
+                offset_of("phone-context"),
+            ))
+        }
+        (_, Some(c)) if c.starts_with('+') => is_global_number_digits(c),
+        (_, Some(c)) => is_domainname(c),
+        _ => true,
+    };
+    if !valid_context {
+        return Err(invalid(Production::Parameter, offset_of("phone-context")));
+    }
+    match tel.extension() {
+        Some(e) if !e.is_empty() && e.chars().all(is_phonedigit) => {}
+        None => {}
+        Some(_) => return Err(invalid(Production::Parameter, offset_of("ext"))),
+    }
+    for ((name, value), offset) in tel.params.iter().zip(&offsets) {
+        let requires_value = ["ext", "isub", "phone-context"].contains(&name.as_str());
+        match value {
+            Some(v) if v.is_empty() => return Err(invalid(Production::Parameter, *offset)),
+            None if requires_value => return Err(invalid(Production::Parameter, *offset)),
+            _ => {}
+        }
+    }
+    Ok(tel)
+}
+
+// visual-separator = "-" / "." / "(" / ")"
+fn is_visual_separator(c: char) -> bool {
+    "-.()".contains(c)
+}
+
+// phonedigit = DIGIT / [ visual-separator ]
+fn is_phonedigit(c: char) -> bool {
+    c.is_ascii_digit() || is_visual_separator(c)
+}
+
+// global-number-digits = "+" *phonedigit DIGIT *phonedigit
+fn is_global_number_digits(s: &str) -> bool {
+    let digits = &s[1..];
+    digits.chars().all(is_phonedigit) && digits.chars().any(|c| c.is_ascii_digit())
+}
+
+// local-number-digits = *phonedigit-hex (HEXDIG / "*" / "#") *phonedigit-hex
+fn is_local_number_digits(s: &str) -> bool {
+    let is_digit = |c: char| c.is_ascii_hexdigit() || c == '*' || c == '#';
+    s.chars().all(|c| is_digit(c) || is_visual_separator(c)) && s.chars().any(is_digit)
+}
+
+// domainname = *( domainlabel "." ) toplabel [ "." ], where the toplabel starts with
+// a letter
+fn is_domainname(s: &str) -> bool {
+    let is_label = |l: &str| {
+        !l.is_empty()
+            && !l.starts_with('-')
+            && !l.ends_with('-')
+            && l.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    };
+    let labels: Vec<&str> = s.strip_suffix('.').unwrap_or(s).split('.').collect();
+    let top = labels[labels.len() - 1];
+    labels.iter().all(|l| is_label(l)) && top.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let t: TelUri = "tel:+1-201-555-0123".parse()?;
+        assert!(t.is_global());
+        assert_eq!("+1-201-555-0123", t.number());
+        assert_eq!("+12015550123", t.digits());
+        assert_eq!(None, t.phone_context());
+
+        let t: TelUri = "tel:7042;phone-context=example.com".parse()?;
+        assert!(!t.is_global());
+        assert_eq!(Some("example.com"), t.phone_context());
+
+        let t: TelUri = "tel:863-1234;phone-context=+1-914-555".parse()?;
+        assert_eq!("8631234", t.digits());
+        assert_eq!(Some("+1-914-555"), t.phone_context());
+
+        let t: TelUri = "TEL:+49(0)30.1234;EXT=56;isub=a%20b;foo;bar=baz".parse()?;
+        assert_eq!(Some("56"), t.extension());
+        assert_eq!(Some("a b"), t.isdn_subaddress());
+        assert_eq!(
+            vec![
+                ("ext", Some("56")),
+                ("isub", Some("a b")),
+                ("foo", None),
+                ("bar", Some("baz"))
+            ],
+            t.params().collect::<Vec<_>>()
+        );
+
+        let t: TelUri = "tel:*21%23;phone-context=+49".parse()?;
+        assert_eq!("*21#", t.number());
+        Ok(())
+    }
+
+    fn parse_error(input: &str) -> (UriErrorKind, Production, usize) {
+        let e = input.parse::<TelUri>().unwrap_err();
+        (e.kind(), e.production(), e.offset())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use uri::error::UriErrorKind::*;
+
+        assert_eq!(
+            (NotAllowed, Production::Scheme, 0),
+            parse_error("http://a/")
+        );
+        assert_eq!(
+            (Invalid, Production::TelephoneNumber, 4),
+            parse_error("tel:")
+        );
+        assert_eq!(
+            (Invalid, Production::TelephoneNumber, 4),
+            parse_error("tel:+")
+        );
+        assert_eq!(
+            (Invalid, Production::TelephoneNumber, 4),
+            parse_error("tel:+-")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Path, 6),
+            parse_error("tel:+1 201")
+        );
+        assert_eq!(
+            (Invalid, Production::TelephoneNumber, 4),
+            parse_error("tel:+1x")
+        );
+        assert_eq!(
+            (NotAllowed, Production::TelephoneNumber, 4),
+            parse_error("tel:7042")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 9),
+            parse_error("tel:7042;phone-context=")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 9),
+            parse_error("tel:7042;phone-context=-example.com")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 9),
+            parse_error("tel:7042;phone-context=example.123")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 9),
+            parse_error("tel:7042;phone-context=+")
+        );
+        assert_eq!(
+            (NotAllowed, Production::Parameter, 20),
+            parse_error("tel:+1-816-555-1212;phone-context=example.com")
+        );
+        assert_eq!(
+            (Invalid, Production::TelephoneNumber, 4),
+            parse_error("tel:g;phone-context=example.com")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 7),
+            parse_error("tel:+1;ext=")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 7),
+            parse_error("tel:+1;ext=1a")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 7),
+            parse_error("tel:+1;ext")
+        );
+        assert_eq!(
+            (NotAllowed, Production::Parameter, 13),
+            parse_error("tel:+1;ext=1;ext=2")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 7),
+            parse_error("tel:+1;a_b=c")
+        );
+        assert_eq!(
+            (Invalid, Production::Parameter, 7),
+            parse_error("tel:+1;=c")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Escape, 13),
+            parse_error("tel:+1;isub=%G0")
+        );
+        assert_eq!(
+            "The telephone number at byte 4 is not allowed.\ntel:7042\n    ^",
+            "tel:7042".parse::<TelUri>().unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,958 @@
+//https://tools.ietf.org/html/rfc6570
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::percent_decode_str;
+use uri::Uri;
+
+/// The value of a template variable: a string, a list or an ordered map of
+/// name/value pairs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateValue {
+    String(String),
+    List(Vec<String>),
+    Map(Vec<(String, String)>),
+}
+
+impl TemplateValue {
+    // Empty lists and maps are undefined, like a missing variable.
+    fn is_defined(&self) -> bool {
+        match self {
+            TemplateValue::String(_) => true,
+            TemplateValue::List(l) => !l.is_empty(),
+            TemplateValue::Map(m) => !m.is_empty(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for TemplateValue {
+    fn from(s: &'a str) -> TemplateValue {
+        TemplateValue::String(s.to_string())
+    }
+}
+
+impl From<String> for TemplateValue {
+    fn from(s: String) -> TemplateValue {
+        TemplateValue::String(s)
+    }
+}
+
+impl<'a> From<Vec<&'a str>> for TemplateValue {
+    fn from(l: Vec<&'a str>) -> TemplateValue {
+        TemplateValue::List(l.into_iter().map(String::from).collect())
+    }
+}
+
+impl<'a> From<Vec<(&'a str, &'a str)>> for TemplateValue {
+    fn from(m: Vec<(&'a str, &'a str)>) -> TemplateValue {
+        TemplateValue::Map(
+            m.into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+}
+
+/// A URI Template of RFC 6570 (levels 1 to 4), which expands into a `Uri` and
+/// can match a `Uri` to extract the variables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UriTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Expression(Operator, Vec<VarSpec>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Label,
+    Path,
+    PathParam,
+    Query,
+    QueryContinuation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct VarSpec {
+    name: String,
+    modifier: Modifier,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Modifier {
+    None,
+    Prefix(usize),
+    Explode,
+}
+
+impl Operator {
+    fn from(c: char) -> Option<Operator> {
+        match c {
+            '+' => Some(Operator::Reserved),
+            '#' => Some(Operator::Fragment),
+            '.' => Some(Operator::Label),
+            '/' => Some(Operator::Path),
+            ';' => Some(Operator::PathParam),
+            '?' => Some(Operator::Query),
+            '&' => Some(Operator::QueryContinuation),
+            _ => None,
+        }
+    }
+
+    fn first(self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved => "",
+            Operator::Fragment => "#",
+            Operator::Label => ".",
+            Operator::Path => "/",
+            Operator::PathParam => ";",
+            Operator::Query => "?",
+            Operator::QueryContinuation => "&",
+        }
+    }
+
+    fn separator(self) -> char {
+        match self {
+            Operator::Simple | Operator::Reserved | Operator::Fragment => ',',
+            Operator::Label => '.',
+            Operator::Path => '/',
+            Operator::PathParam => ';',
+            Operator::Query | Operator::QueryContinuation => '&',
+        }
+    }
+
+    fn is_named(self) -> bool {
+        matches!(
+            self,
+            Operator::PathParam | Operator::Query | Operator::QueryContinuation
+        )
+    }
+
+    fn if_empty(self) -> &'static str {
+        match self {
+            Operator::Query | Operator::QueryContinuation => "=",
+            _ => "",
+        }
+    }
+
+    fn allows_reserved(self) -> bool {
+        matches!(self, Operator::Reserved | Operator::Fragment)
+    }
+}
+
+impl UriTemplate {
+    /// Parses a template. The errors carry the template as input.
+    pub fn parse(template: &str) -> Result<UriTemplate> {
+        match parts(template) {
+            Ok(parts) => Ok(UriTemplate { parts }),
+            Err(e) => Err(e.with_input(template.as_bytes())),
+        }
+    }
+
+    /// Expands the template into a string. Missing variables are undefined.
+    pub fn expand_to_string(&self, variables: &HashMap<String, TemplateValue>) -> String {
+        let mut expanded = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(l) => expanded.push_str(l),
+                Part::Expression(op, varspecs) => {
+                    expand_expression(&mut expanded, *op, varspecs, variables)
+                }
+            }
+        }
+        expanded
+    }
+
+    /// Expands the template and parses the result as a URI reference.
+    pub fn expand(&self, variables: &HashMap<String, TemplateValue>) -> Result<Uri> {
+        Uri::parse(self.expand_to_string(variables).as_bytes())
+    }
+
+    /// Extracts the variables from a URI that this template expands to, or returns
+    /// `None` if it does not match. Variables that are not part of the URI are
+    /// left out.
+    pub fn match_uri(&self, uri: &Uri) -> Option<HashMap<String, TemplateValue>> {
+        let mut variables: HashMap<String, TemplateValue> = HashMap::new();
+        match match_parts(&self.parts, &uri.to_string(), &mut variables) {
+            true => Some(variables),
+            false => None,
+        }
+    }
+}
+
+impl std::str::FromStr for UriTemplate {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<UriTemplate> {
+        UriTemplate::parse(s)
+    }
+}
+
+// URI-Template = *( literals / expression )
+fn parts(template: &str) -> Result<Vec<Part>> {
+    let mut parts: Vec<Part> = Vec::new();
+    let mut offset = 0;
+    while offset < template.len() {
+        let rest = &template[offset..];
+        match rest.find('{') {
+            Some(0) => {
+                let end = match rest.find('}') {
+                    Some(end) => end,
+                    None => {
+                        let end = template.len();
+                        return Err(UriError::unexpected(Production::Expression, end, None));
+                    }
+                };
+                parts.push(expression(&rest[1..end]).map_err(|e| e.shifted(offset + 1))?);
+                offset += end + 1;
+            }
+            Some(start) => {
+                parts.push(literal(&rest[..start]).map_err(|e| e.shifted(offset))?);
+                offset += start;
+            }
+            None => {
+                parts.push(literal(rest).map_err(|e| e.shifted(offset))?);
+                offset = template.len();
+            }
+        }
+    }
+    Ok(parts)
+}
+
+// literals = any character except CTL, SP, DQUOTE, "'", "%" (aside from
+// pct-encoded), "<", ">", "\", "^", "`", "{", "|" and "}"
+fn literal(l: &str) -> Result<Part> {
+    if let Some(i) = l.find(|c: char| c.is_ascii_control() || " \"'<>\\^`|}".contains(c)) {
+        return Err(unexpected(Production::Template, l, i));
+    }
+    Ok(Part::Literal(encode(l, true)))
+}
+
+// expression = "{" [ operator ] variable-list "}", without the braces. Error offsets
+// are the ones in the expression.
+fn expression(e: &str) -> Result<Part> {
+    let op = match e.chars().next() {
+        Some(c) => match Operator::from(c) {
+            Some(op) => op,
+            None => match c {
+                '=' | ',' | '!' | '@' | '|' => {
+                    return Err(unexpected(Production::Expression, e, 0))
+                }
+                _ => Operator::Simple,
+            },
+        },
+        None => {
+            return Err(UriError::unexpected(
+                Production::Expression,
+                0,
+                Some(Char::Ascii(b'}')),
+            ))
+        }
+    };
+    let mut offset = match op {
+        Operator::Simple => 0,
+        _ => 1,
+    };
+
+    let mut varspecs: Vec<VarSpec> = Vec::new();
+    for spec in e[offset..].split(',') {
+        let (name, modifier) = match spec.find(':') {
+            Some(i) => match spec[i + 1..].parse::<usize>() {
+                Ok(n) if n > 0 && n < 10000 => (&spec[..i], Modifier::Prefix(n)),
+                _ => return Err(invalid(Production::Prefix, e, offset + i + 1)),
+            },
+            None => match spec.ends_with('*') {
+                true => (&spec[..spec.len() - 1], Modifier::Explode),
+                false => (spec, Modifier::None),
+            },
+        };
+        if !is_varname(name) {
+            return Err(invalid(Production::VariableName, e, offset));
+        }
+        varspecs.push(VarSpec {
+            name: name.to_string(),
+            modifier,
+        });
+        offset += spec.len() + 1;
+    }
+    Ok(Part::Expression(op, varspecs))
+}
+
+fn unexpected(production: Production, s: &str, i: usize) -> UriError {
+    let byte = s.as_bytes().get(i).cloned();
+    UriError::new(UriErrorKind::UnexpectedByte, production, i, byte)
+}
+
+fn invalid(production: Production, s: &str, i: usize) -> UriError {
+    UriError::new(
+        UriErrorKind::Invalid,
+        production,
+        i,
+        s.as_bytes().get(i).cloned(),
+    )
+}
+
+// varname = varchar *( ["."] varchar ), varchar = ALPHA / DIGIT / "_" / pct-encoded
+fn is_varname(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%' {
+            if i + 2 >= bytes.len() {
+                return false;
+            }
+            if !bytes[i + 1].is_ascii_hexdigit() || !bytes[i + 2].is_ascii_hexdigit() {
+                return false;
+            }
+            i += 3;
+            continue;
+        }
+        let dot_allowed = i > 0 && i < bytes.len() - 1 && bytes[i - 1] != b'.';
+        if !(b.is_ascii_alphanumeric() || b == b'_' || (b == b'.' && dot_allowed)) {
+            return false;
+        }
+        i += 1;
+    }
+    !bytes.is_empty()
+}
+
+fn expand_expression(
+    expanded: &mut String,
+    op: Operator,
+    varspecs: &[VarSpec],
+    variables: &HashMap<String, TemplateValue>,
+) {
+    let allow_reserved = op.allows_reserved();
+    let mut first = true;
+    for varspec in varspecs {
+        let value = match variables.get(&varspec.name) {
+            Some(v) if v.is_defined() => v,
+            _ => continue,
+        };
+        match first {
+            true => expanded.push_str(op.first()),
+            false => expanded.push(op.separator()),
+        }
+        first = false;
+
+        let items: Vec<(Option<&String>, &String)> = match value {
+            TemplateValue::String(s) => {
+                if op.is_named() {
+                    expanded.push_str(&varspec.name);
+                    if s.is_empty() {
+                        expanded.push_str(op.if_empty());
+                        continue;
+                    }
+                    expanded.push('=');
+                }
+                let s: String = match varspec.modifier {
+                    Modifier::Prefix(n) => s.chars().take(n).collect(),
+                    _ => s.clone(),
+                };
+                expanded.push_str(&encode(&s, allow_reserved));
+                continue;
+            }
+            TemplateValue::List(l) => l.iter().map(|v| (None, v)).collect(),
+            TemplateValue::Map(m) => m.iter().map(|(k, v)| (Some(k), v)).collect(),
+        };
+
+        if varspec.modifier != Modifier::Explode {
+            if op.is_named() {
+                expanded.push_str(&varspec.name);
+                expanded.push('=');
+            }
+            let flattened = items
+                .into_iter()
+                .flat_map(|(k, v)| k.into_iter().chain(Some(v)));
+            for (i, item) in flattened.enumerate() {
+                if i > 0 {
+                    expanded.push(',');
+                }
+                expanded.push_str(&encode(item, allow_reserved));
+            }
+            continue;
+        }
+        for (i, (k, v)) in items.into_iter().enumerate() {
+            if i > 0 {
+                expanded.push(op.separator());
+            }
+            match (op.is_named(), k) {
+                (true, None) => expanded.push_str(&varspec.name),
+                (_, Some(k)) => expanded.push_str(&encode(k, allow_reserved)),
+                (false, None) => {
+                    expanded.push_str(&encode(v, allow_reserved));
+                    continue;
+                }
+            }
+            match op.is_named() && v.is_empty() {
+                true => expanded.push_str(op.if_empty()),
+                false => {
+                    expanded.push('=');
+                    expanded.push_str(&encode(v, allow_reserved));
+                }
+            }
+        }
+    }
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~".contains(c)
+}
+
+fn is_reserved(c: char) -> bool {
+    ":/?#[]@!$&'()*+,;=".contains(c)
+}
+
+// Percent-encodes all characters but unreserved ones, or, if reserved characters
+// are allowed, keeps those and existing escapes as well.
+fn encode(s: &str, allow_reserved: bool) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    let bytes = s.as_bytes();
+    for (i, c) in s.char_indices() {
+        if is_unreserved(c) || (allow_reserved && is_reserved(c)) {
+            encoded.push(c);
+            continue;
+        }
+        let is_escape = c == '%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+        if allow_reserved && is_escape {
+            encoded.push(c);
+            continue;
+        }
+        let mut buf = [0; 4];
+        for b in c.encode_utf8(&mut buf).bytes() {
+            write!(encoded, "%{:02X}", b).unwrap();
+        }
+    }
+    encoded
+}
+
+// Matches the parts from left to right. An expression extends up to the first
+// occurrence of the next literal or operator prefix (RFC 6570, section 1.5), so
+// every part is matched once instead of trying every split of the input.
+fn match_parts(
+    parts: &[Part],
+    input: &str,
+    variables: &mut HashMap<String, TemplateValue>,
+) -> bool {
+    let mut pos = 0;
+    for (i, part) in parts.iter().enumerate() {
+        let rest = &input[pos..];
+        match part {
+            Part::Literal(l) => {
+                if !rest.starts_with(l.as_str()) {
+                    return false;
+                }
+                pos += l.len();
+            }
+            Part::Expression(op, varspecs) => {
+                let end = expansion_len(rest, *op, &parts[i + 1..]);
+                if !match_expression(&rest[..end], *op, varspecs, variables) {
+                    return false;
+                }
+                pos += end;
+            }
+        }
+    }
+    pos == input.len()
+}
+
+// Returns the length of the expansion of an expression with the operator at the
+// start of the input. It ends at the next anchor: a literal or the prefix of an
+// operator like "/" or "?". Expressions without prefix between this one and the
+// anchor are ambiguous; this one takes as many characters as it allows.
+fn expansion_len(input: &str, op: Operator, following: &[Part]) -> usize {
+    if !input.starts_with(op.first()) {
+        return 0;
+    }
+    let start = op.first().len();
+    let anchor = following.iter().find_map(|p| match p {
+        Part::Literal(l) => Some(l.as_str()),
+        Part::Expression(o, _) if !o.first().is_empty() => Some(o.first()),
+        Part::Expression(_, _) => None,
+    });
+    let limit = match anchor.and_then(|a| input[start..].find(a)) {
+        Some(i) => start + i,
+        None => input.len(),
+    };
+    match following.first() {
+        Some(Part::Expression(o, _)) if o.first().is_empty() => {
+            match input[start..limit].find(|c| !is_expansion_char(op, c)) {
+                Some(i) => start + i,
+                None => limit,
+            }
+        }
+        _ => limit,
+    }
+}
+
+fn is_expansion_char(op: Operator, c: char) -> bool {
+    is_unreserved(c)
+        || c == '%'
+        || c == op.separator()
+        || c == ','
+        || c == '='
+        || (op.allows_reserved() && is_reserved(c))
+}
+
+fn match_expression(
+    expansion: &str,
+    op: Operator,
+    varspecs: &[VarSpec],
+    variables: &mut HashMap<String, TemplateValue>,
+) -> bool {
+    if expansion.is_empty() {
+        return true;
+    }
+    if !expansion.starts_with(op.first()) {
+        return false;
+    }
+    let body = &expansion[op.first().len()..];
+    if !body.chars().all(|c| is_expansion_char(op, c)) {
+        return false;
+    }
+    let items: Vec<&str> = body.split(op.separator()).collect();
+
+    match op.is_named() {
+        true => match_named(&items, varspecs, variables),
+        false => match_unnamed(&items, op, varspecs, variables),
+    }
+}
+
+fn match_unnamed(
+    items: &[&str],
+    op: Operator,
+    varspecs: &[VarSpec],
+    variables: &mut HashMap<String, TemplateValue>,
+) -> bool {
+    // With "," as separator, the items of a list are indistinguishable from
+    // further variables, so lists are only taken by the last variable.
+    let mut i = 0;
+    for (n, varspec) in varspecs.iter().enumerate() {
+        if i >= items.len() {
+            break;
+        }
+        let is_last = n == varspecs.len() - 1;
+        if varspec.modifier == Modifier::Explode && is_last {
+            let value = match items[i..].iter().all(|item| item.contains('=')) {
+                true => {
+                    let mut map: Vec<(String, String)> = Vec::new();
+                    for item in &items[i..] {
+                        let (k, v) = split_pair(item);
+                        match (decode(k), decode(v)) {
+                            (Some(k), Some(v)) => map.push((k, v)),
+                            _ => return false,
+                        }
+                    }
+                    TemplateValue::Map(map)
+                }
+                false => match decode_all(&items[i..]) {
+                    Some(l) => TemplateValue::List(l),
+                    None => return false,
+                },
+            };
+            variables.insert(varspec.name.clone(), value);
+            i = items.len();
+            break;
+        }
+        if is_last && op.separator() == ',' && items.len() - i > 1 {
+            match decode_all(&items[i..]) {
+                Some(l) => variables.insert(varspec.name.clone(), TemplateValue::List(l)),
+                None => return false,
+            };
+            i = items.len();
+            break;
+        }
+        match value_of(items[i]) {
+            Some(v) => variables.insert(varspec.name.clone(), v),
+            None => return false,
+        };
+        i += 1;
+    }
+    i == items.len()
+}
+
+fn match_named(
+    items: &[&str],
+    varspecs: &[VarSpec],
+    variables: &mut HashMap<String, TemplateValue>,
+) -> bool {
+    let mut exploded: Option<&VarSpec> = None;
+    let mut lists: HashMap<String, Vec<String>> = HashMap::new();
+    let mut maps: Vec<(String, String)> = Vec::new();
+    for item in items {
+        let (name, raw_value) = split_pair(item);
+        let (name, value) = match (decode(name), decode(raw_value)) {
+            (Some(n), Some(v)) => (n, v),
+            _ => return false,
+        };
+        match varspecs.iter().find(|v| v.name == name) {
+            Some(varspec) => match varspec.modifier {
+                Modifier::Explode => lists.entry(name).or_default().push(value),
+                _ => match value_of(raw_value) {
+                    Some(value) => {
+                        variables.insert(name, value);
+                    }
+                    None => return false,
+                },
+            },
+            None => {
+                // Pairs with unknown names belong to an exploded map.
+                if exploded.is_none() {
+                    exploded = varspecs.iter().find(|v| v.modifier == Modifier::Explode);
+                }
+                match exploded {
+                    Some(_) => maps.push((name, value)),
+                    None => return false,
+                }
+            }
+        }
+    }
+    for (name, list) in lists {
+        variables.insert(name, TemplateValue::List(list));
+    }
+    if let Some(varspec) = exploded {
+        variables.insert(varspec.name.clone(), TemplateValue::Map(maps));
+    }
+    true
+}
+
+fn split_pair(item: &str) -> (&str, &str) {
+    match item.find('=') {
+        Some(i) => (&item[..i], &item[i + 1..]),
+        None => (item, ""),
+    }
+}
+
+// An unexploded list is expanded with "," between its items.
+fn value_of(raw: &str) -> Option<TemplateValue> {
+    match raw.contains(',') {
+        true => decode_all(&raw.split(',').collect::<Vec<&str>>()).map(TemplateValue::List),
+        false => decode(raw).map(TemplateValue::String),
+    }
+}
+
+fn decode(s: &str) -> Option<String> {
+    percent_decode_str(s).ok().map(|s| s.into_owned())
+}
+
+fn decode_all(items: &[&str]) -> Option<Vec<String>> {
+    items.iter().map(|item| decode(item)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> HashMap<String, TemplateValue> {
+        // https://tools.ietf.org/html/rfc6570#section-3.2
+        let mut v: HashMap<String, TemplateValue> = HashMap::new();
+        v.insert("count".to_string(), vec!["one", "two", "three"].into());
+        v.insert("dom".to_string(), vec!["example", "com"].into());
+        v.insert("dub".to_string(), "me/too".into());
+        v.insert("hello".to_string(), "Hello World!".into());
+        v.insert("half".to_string(), "50%".into());
+        v.insert("var".to_string(), "value".into());
+        v.insert("who".to_string(), "fred".into());
+        v.insert("base".to_string(), "http://example.com/home/".into());
+        v.insert("path".to_string(), "/foo/bar".into());
+        v.insert("list".to_string(), vec!["red", "green", "blue"].into());
+        v.insert(
+            "keys".to_string(),
+            vec![("semi", ";"), ("dot", "."), ("comma", ",")].into(),
+        );
+        v.insert("v".to_string(), "6".into());
+        v.insert("x".to_string(), "1024".into());
+        v.insert("y".to_string(), "768".into());
+        v.insert("empty".to_string(), "".into());
+        v.insert("empty_keys".to_string(), TemplateValue::Map(Vec::new()));
+        v
+    }
+
+    fn assert_expansions(examples: &[(&str, &str)]) -> Result<()> {
+        let v = variables();
+        for (template, expected) in examples {
+            let t = UriTemplate::parse(template)?;
+            assert_eq!(*expected, t.expand_to_string(&v), "{}", template);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_level1_and_level2() -> Result<()> {
+        assert_expansions(&[
+            ("{var}", "value"),
+            ("{hello}", "Hello%20World%21"),
+            ("{half}", "50%25"),
+            ("O{empty}X", "OX"),
+            ("O{undef}X", "OX"),
+            ("{x,y}", "1024,768"),
+            ("{x,hello,y}", "1024,Hello%20World%21,768"),
+            ("?{x,empty}", "?1024,"),
+            ("?{x,undef}", "?1024"),
+            ("?{undef,y}", "?768"),
+            ("{var:3}", "val"),
+            ("{var:30}", "value"),
+            ("{list}", "red,green,blue"),
+            ("{list*}", "red,green,blue"),
+            ("{keys}", "semi,%3B,dot,.,comma,%2C"),
+            ("{keys*}", "semi=%3B,dot=.,comma=%2C"),
+            ("{+var}", "value"),
+            ("{+hello}", "Hello%20World!"),
+            ("{+half}", "50%25"),
+            ("{base}index", "http%3A%2F%2Fexample.com%2Fhome%2Findex"),
+            ("{+base}index", "http://example.com/home/index"),
+            ("O{+empty}X", "OX"),
+            ("{+path}/here", "/foo/bar/here"),
+            ("here?ref={+path}", "here?ref=/foo/bar"),
+            ("up{+path}{var}/here", "up/foo/barvalue/here"),
+            ("{+x,hello,y}", "1024,Hello%20World!,768"),
+            ("{+path,x}/here", "/foo/bar,1024/here"),
+            ("{+path:6}/here", "/foo/b/here"),
+            ("{+list}", "red,green,blue"),
+            ("{+keys*}", "semi=;,dot=.,comma=,"),
+            ("{#var}", "#value"),
+            ("{#hello}", "#Hello%20World!"),
+            ("{#half}", "#50%25"),
+            ("foo{#empty}", "foo#"),
+            ("foo{#undef}", "foo"),
+            ("{#x,hello,y}", "#1024,Hello%20World!,768"),
+            ("{#path,x}/here", "#/foo/bar,1024/here"),
+            ("{#path:6}/here", "#/foo/b/here"),
+            ("{#list*}", "#red,green,blue"),
+            ("{#keys*}", "#semi=;,dot=.,comma=,"),
+        ])
+    }
+
+    #[test]
+    fn test_level3_and_level4() -> Result<()> {
+        assert_expansions(&[
+            ("{.who}", ".fred"),
+            ("{.who,who}", ".fred.fred"),
+            ("{.half,who}", ".50%25.fred"),
+            ("www{.dom*}", "www.example.com"),
+            ("X{.var}", "X.value"),
+            ("X{.empty}", "X."),
+            ("X{.undef}", "X"),
+            ("X{.var:3}", "X.val"),
+            ("X{.list}", "X.red,green,blue"),
+            ("X{.list*}", "X.red.green.blue"),
+            ("X{.keys}", "X.semi,%3B,dot,.,comma,%2C"),
+            ("X{.keys*}", "X.semi=%3B.dot=..comma=%2C"),
+            ("X{.empty_keys}", "X"),
+            ("X{.empty_keys*}", "X"),
+            ("{/who}", "/fred"),
+            ("{/who,who}", "/fred/fred"),
+            ("{/half,who}", "/50%25/fred"),
+            ("{/who,dub}", "/fred/me%2Ftoo"),
+            ("{/var}", "/value"),
+            ("{/var,empty}", "/value/"),
+            ("{/var,undef}", "/value"),
+            ("{/var,x}/here", "/value/1024/here"),
+            ("{/var:1,var}", "/v/value"),
+            ("{/list}", "/red,green,blue"),
+            ("{/list*}", "/red/green/blue"),
+            ("{/list*,path:4}", "/red/green/blue/%2Ffoo"),
+            ("{/keys}", "/semi,%3B,dot,.,comma,%2C"),
+            ("{/keys*}", "/semi=%3B/dot=./comma=%2C"),
+            ("{;who}", ";who=fred"),
+            ("{;half}", ";half=50%25"),
+            ("{;empty}", ";empty"),
+            ("{;v,empty,who}", ";v=6;empty;who=fred"),
+            ("{;v,bar,who}", ";v=6;who=fred"),
+            ("{;x,y}", ";x=1024;y=768"),
+            ("{;x,y,empty}", ";x=1024;y=768;empty"),
+            ("{;x,y,undef}", ";x=1024;y=768"),
+            ("{;hello:5}", ";hello=Hello"),
+            ("{;list}", ";list=red,green,blue"),
+            ("{;list*}", ";list=red;list=green;list=blue"),
+            ("{;keys}", ";keys=semi,%3B,dot,.,comma,%2C"),
+            ("{;keys*}", ";semi=%3B;dot=.;comma=%2C"),
+            ("{?who}", "?who=fred"),
+            ("{?half}", "?half=50%25"),
+            ("{?x,y}", "?x=1024&y=768"),
+            ("{?x,y,empty}", "?x=1024&y=768&empty="),
+            ("{?x,y,undef}", "?x=1024&y=768"),
+            ("{?var:3}", "?var=val"),
+            ("{?list}", "?list=red,green,blue"),
+            ("{?list*}", "?list=red&list=green&list=blue"),
+            ("{?keys}", "?keys=semi,%3B,dot,.,comma,%2C"),
+            ("{?keys*}", "?semi=%3B&dot=.&comma=%2C"),
+            ("{&who}", "&who=fred"),
+            ("{&half}", "&half=50%25"),
+            ("?fixed=yes{&x}", "?fixed=yes&x=1024"),
+            ("{&x,y,empty}", "&x=1024&y=768&empty="),
+            ("{&var:3}", "&var=val"),
+            ("{&list}", "&list=red,green,blue"),
+            ("{&list*}", "&list=red&list=green&list=blue"),
+            ("{&keys}", "&keys=semi,%3B,dot,.,comma,%2C"),
+            ("{&keys*}", "&semi=%3B&dot=.&comma=%2C"),
+        ])
+    }
+
+    #[test]
+    fn test_expand() -> Result<()> {
+        let t: UriTemplate = "https://api.example.com/users/{id}/repos{?page,per_page}".parse()?;
+        let mut v: HashMap<String, TemplateValue> = HashMap::new();
+        v.insert("id".to_string(), "jo hn".into());
+        v.insert("page".to_string(), "2".into());
+        let u = t.expand(&v)?;
+        assert_eq!(
+            "https://api.example.com/users/jo%20hn/repos?page=2",
+            u.to_string()
+        );
+        assert_eq!("/users/jo hn/repos", u.path().decode_utf8()?);
+        Ok(())
+    }
+
+    fn parse_error(template: &str) -> (UriErrorKind, Production, usize) {
+        let e = UriTemplate::parse(template).unwrap_err();
+        (e.kind(), e.production(), e.offset())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use uri::error::UriErrorKind::*;
+
+        assert_eq!(
+            (UnexpectedEnd, Production::Expression, 4),
+            parse_error("{var")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Template, 3),
+            parse_error("var}")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Expression, 1),
+            parse_error("{}")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Expression, 1),
+            parse_error("{=var}")
+        );
+        assert_eq!((Invalid, Production::Prefix, 5), parse_error("{var:0}"));
+        assert_eq!(
+            (Invalid, Production::Prefix, 10),
+            parse_error("/x{?a,var:10000}")
+        );
+        assert_eq!(
+            (Invalid, Production::VariableName, 1),
+            parse_error("{va r}")
+        );
+        assert_eq!((Invalid, Production::VariableName, 2), parse_error("{.}"));
+        assert_eq!(
+            (Invalid, Production::VariableName, 4),
+            parse_error("{/a,a..b}")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Template, 1),
+            parse_error("a b")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Template, 1),
+            parse_error("a'b{x}")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Template, 1),
+            parse_error("a|b")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Template, 3),
+            parse_error("{x}\\")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Template, 3),
+            parse_error("{x}^`")
+        );
+        assert_eq!(
+            "Unexpected character '|' in URI template at byte 1.\na|b\n ^",
+            UriTemplate::parse("a|b").unwrap_err().to_string()
+        );
+
+        let t = UriTemplate::parse("a%27b{x}%7C").unwrap();
+        let mut v: HashMap<String, TemplateValue> = HashMap::new();
+        v.insert("x".to_string(), "c".into());
+        assert_eq!("a%27bc%7C", t.expand_to_string(&v));
+    }
+
+    #[test]
+    fn test_match_uri() -> Result<()> {
+        let t: UriTemplate = "https://api.example.com/users/{id}/repos{?page,per_page}".parse()?;
+        let u: Uri = "https://api.example.com/users/jo%20hn/repos?per_page=10&page=2".parse()?;
+        let v = t.match_uri(&u).unwrap();
+        assert_eq!(Some(&"jo hn".into()), v.get("id"));
+        assert_eq!(Some(&"2".into()), v.get("page"));
+        assert_eq!(Some(&"10".into()), v.get("per_page"));
+
+        let u: Uri = "https://api.example.com/users/42/repos".parse()?;
+        let v = t.match_uri(&u).unwrap();
+        assert_eq!(Some(&"42".into()), v.get("id"));
+        assert_eq!(None, v.get("page"));
+
+        let u: Uri = "https://api.example.com/users/42/gists".parse()?;
+        assert_eq!(None, t.match_uri(&u));
+        let u: Uri = "https://api.example.com/users/a/b/repos".parse()?;
+        assert_eq!(None, t.match_uri(&u));
+
+        let t: UriTemplate = "/{a}{b}{c}{d}/x".parse()?;
+        let u: Uri = format!("/{}/x", "a".repeat(3)).parse()?;
+        let v = t.match_uri(&u).unwrap();
+        assert_eq!(Some(&"aaa".into()), v.get("a"));
+        assert_eq!(None, v.get("b"));
+        let u: Uri = "/a/b/x".parse()?;
+        assert_eq!(None, t.match_uri(&u));
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_long_input() -> Result<()> {
+        // Each expression is matched once, so a long input that does not match
+        // fails in linear time instead of trying every split between expressions.
+        let t: UriTemplate = "/{a}{b}{c}{d}/x{?q}".parse()?;
+        let u: Uri = format!("/{}/y", "a".repeat(100_000)).parse()?;
+        assert_eq!(None, t.match_uri(&u));
+        let u: Uri = format!("/{}/x?q={}", "a".repeat(100_000), "b".repeat(100_000)).parse()?;
+        let v = t.match_uri(&u).unwrap();
+        assert_eq!(Some(&"b".repeat(100_000).into()), v.get("q"));
+
+        let t: UriTemplate = "{/p*}/{x}/{y}.{z}".parse()?;
+        let u: Uri = format!("/{}/y", "a/".repeat(50_000)).parse()?;
+        assert_eq!(None, t.match_uri(&u));
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_roundtrip() -> Result<()> {
+        let v = variables();
+        for template in [
+            "/files{/list*}{?x,y}",
+            "/a/{var}{.who}{#hello}",
+            "{/who}{;keys*}",
+            "/search{?keys*}",
+            "/{x}/{+path}",
+            "/c{/count}{?list*}",
+        ]
+        .iter()
+        {
+            let t = UriTemplate::parse(template)?;
+            let u = t.expand(&v)?;
+            let matched = t.match_uri(&u).unwrap();
+            assert_eq!(u, t.expand(&matched)?, "{}", template);
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,29 @@
-use common_failures::Result;
 use std::convert::From;
 use std::io::Read;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
 
 pub trait TokenStream<T> {
     fn next(&mut self) -> Result<Option<T>>;
 }
 
+/// A token that is read from a number of input bytes.
+pub trait Token {
+    fn width(&self) -> usize;
+}
+
+impl Token for u8 {
+    fn width(&self) -> usize {
+        1
+    }
+}
+
 pub struct ByteStream<R: Read> {
     read: R,
     buffer: [u8; 1],
+    offset: usize,
 }
 
 impl<R: Read> From<R> for ByteStream<R> {
@@ -16,23 +31,45 @@ impl<R: Read> From<R> for ByteStream<R> {
         ByteStream {
             read: from,
             buffer: [0],
+            offset: 0,
         }
     }
 }
 
 impl<R: Read> TokenStream<u8> for ByteStream<R> {
     fn next(&mut self) -> Result<Option<u8>> {
-        match self.read.read(&mut self.buffer)? {
-            0 => Ok(None),
-            1 => Ok(Some(self.buffer[0])),
-            _ => Err(format_err!("")),
+        match self.read.read(&mut self.buffer) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.offset += 1;
+                Ok(Some(self.buffer[0]))
+            }
+            Err(e) => Err(UriError::new(
+                UriErrorKind::Io(e.kind()),
+                Production::Uri,
+                self.offset,
+                None,
+            )),
         }
     }
 }
 
+pub struct EmptyStream;
+
+impl<T> TokenStream<T> for EmptyStream {
+    fn next(&mut self) -> Result<Option<T>> {
+        Ok(None)
+    }
+}
+
+// The buffer holds pushed back tokens in reverse order, so the next token is at its
+// end and pop and push don't have to shift the remaining tokens. The offset is the
+// number of input bytes before the next token; it wraps, so that tokens that were not
+// read from the input can be pushed, too.
 pub struct TokenBuffer<T, S: TokenStream<T>> {
     pub(crate) stream: S,
     pub(crate) buffer: Vec<T>,
+    pub(crate) offset: usize,
 }
 
 impl<R: Read> From<R> for TokenBuffer<u8, ByteStream<R>> {
@@ -40,29 +77,57 @@ impl<R: Read> From<R> for TokenBuffer<u8, ByteStream<R>> {
         TokenBuffer {
             stream: ByteStream::from(from),
             buffer: Vec::new(),
+            offset: 0,
         }
     }
 }
 
-impl<T, S: TokenStream<T>> TokenBuffer<T, S> {
+impl<T> From<Vec<T>> for TokenBuffer<T, EmptyStream> {
+    fn from(mut from: Vec<T>) -> TokenBuffer<T, EmptyStream> {
+        from.reverse();
+        TokenBuffer {
+            stream: EmptyStream,
+            buffer: from,
+            offset: 0,
+        }
+    }
+}
+
+impl<T: Token, S: TokenStream<T>> TokenBuffer<T, S> {
     pub fn pop(&mut self) -> Result<Option<T>> {
-        let token;
-        if self.buffer.is_empty() {
-            token = self.stream.next()?;
-        } else {
-            token = Some(self.buffer.remove(0));
+        let token = match self.buffer.pop() {
+            Some(token) => Some(token),
+            None => self.stream.next()?,
+        };
+        if let Some(t) = &token {
+            self.offset = self.offset.wrapping_add(t.width());
         }
         Ok(token)
     }
 
     pub fn push(&mut self, token: T) {
-        self.buffer.insert(0, token);
+        self.offset = self.offset.wrapping_sub(token.width());
+        self.buffer.push(token);
+    }
+
+    pub fn push_tokens(&mut self, tokens: Vec<T>) {
+        let width = tokens.iter().map(|t| t.width()).sum();
+        self.offset = self.offset.wrapping_sub(width);
+        self.buffer.extend(tokens.into_iter().rev());
     }
 
-    pub fn push_tokens(&mut self, mut tokens: Vec<T>) {
-        for i in 0..tokens.len() {
-            self.buffer.insert(i, tokens.remove(0));
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T: Token + Clone, S: TokenStream<T>> TokenBuffer<T, S> {
+    pub fn peek(&mut self) -> Result<Option<T>> {
+        let token = self.pop()?;
+        if let Some(t) = &token {
+            self.push(t.clone());
         }
+        Ok(token)
     }
 }
 
@@ -74,22 +139,11 @@ mod tests {
     fn test_from() {
         let mut tb = TokenBuffer::from("Hello World".as_bytes());
 
-        let mut word1: Vec<u8> = Vec::new();
-        word1.push(tb.pop().unwrap().unwrap());
-        word1.push(tb.pop().unwrap().unwrap());
-        word1.push(tb.pop().unwrap().unwrap());
-        word1.push(tb.pop().unwrap().unwrap());
-        word1.push(tb.pop().unwrap().unwrap());
+        let word1: Vec<u8> = (0..5).map(|_| tb.pop().unwrap().unwrap()).collect();
 
-        let mut blank: Vec<u8> = Vec::new();
-        blank.push(tb.pop().unwrap().unwrap());
+        let blank: Vec<u8> = (0..1).map(|_| tb.pop().unwrap().unwrap()).collect();
 
-        let mut word2: Vec<u8> = Vec::new();
-        word2.push(tb.pop().unwrap().unwrap());
-        word2.push(tb.pop().unwrap().unwrap());
-        word2.push(tb.pop().unwrap().unwrap());
-        word2.push(tb.pop().unwrap().unwrap());
-        word2.push(tb.pop().unwrap().unwrap());
+        let word2: Vec<u8> = (0..5).map(|_| tb.pop().unwrap().unwrap()).collect();
 
         assert_eq!(None, tb.pop().unwrap());
 
@@ -97,7 +151,27 @@ mod tests {
         tb.push_tokens(blank);
         tb.push_tokens(word1);
 
-        let foo = tb.buffer;
-        println!("{:?}", foo)
+        let mut all: Vec<u8> = Vec::new();
+        while let Some(b) = tb.pop().unwrap() {
+            all.push(b);
+        }
+        assert_eq!(b"Hello World".to_vec(), all);
+    }
+
+    #[test]
+    fn test_push() {
+        let mut tb = TokenBuffer::from(b"cde".to_vec());
+        assert_eq!(Some(b'c'), tb.pop().unwrap());
+        assert_eq!(1, tb.offset());
+        tb.push(b'c');
+        tb.push(b'b');
+        assert_eq!(-1, tb.offset() as isize);
+        tb.push_tokens(b"xa".to_vec());
+        assert_eq!(Some(b'x'), tb.peek().unwrap());
+        let mut all: Vec<u8> = Vec::new();
+        while let Some(t) = tb.pop().unwrap() {
+            all.push(t);
+        }
+        assert_eq!(b"xabcde".to_vec(), all);
     }
 }
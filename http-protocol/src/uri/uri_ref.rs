@@ -0,0 +1,693 @@
+//https://tools.ietf.org/html/rfc3986#appendix-A
+
+use std::convert::TryFrom;
+use std::net::Ipv6Addr;
+use std::ops::Range;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::error::UriErrorKind;
+use uri::percent::encoded_chars;
+use uri::Authority;
+use uri::Domainlabel;
+use uri::Fragment;
+use uri::Host;
+use uri::Hostname;
+use uri::Hostport;
+use uri::IPLiteral;
+use uri::IPv4address;
+use uri::IPv6address;
+use uri::IPvFuture;
+use uri::Param;
+use uri::PathSegments;
+use uri::Port;
+use uri::Query;
+use uri::RegName;
+use uri::Scheme;
+use uri::Segment;
+use uri::Toplabel;
+use uri::Uri;
+use uri::Userinfo;
+use uri::ZoneId;
+
+/// A URI reference borrowed from the buffer it was parsed from. It accepts the
+/// same RFC 3986 grammar as `Uri::parse`, but only stores the ranges of the
+/// components, so parsing does not allocate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UriRef<'a> {
+    source: &'a str,
+    scheme: Option<Range<usize>>,
+    authority: Option<Range<usize>>,
+    userinfo: Option<Range<usize>>,
+    host: Option<Range<usize>>,
+    port: Option<Range<usize>>,
+    path: Range<usize>,
+    query: Option<Range<usize>>,
+    fragment: Option<Range<usize>>,
+    // The address of an IPv6 host, which was parsed to validate it.
+    ipv6: Option<Ipv6Addr>,
+}
+
+impl<'a> UriRef<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<UriRef<'a>> {
+        UriRef::parse_ranges(bytes).map_err(|e| e.with_input(bytes))
+    }
+
+    pub fn parse_str(s: &'a str) -> Result<UriRef<'a>> {
+        UriRef::parse(s.as_bytes())
+    }
+
+    fn parse_ranges(bytes: &'a [u8]) -> Result<UriRef<'a>> {
+        let mut p = Parser { bytes, pos: 0 };
+        let mut u = UriRef {
+            source: "",
+            scheme: None,
+            authority: None,
+            userinfo: None,
+            host: None,
+            port: None,
+            path: 0..0,
+            query: None,
+            fragment: None,
+            ipv6: None,
+        };
+
+        u.scheme = p.scheme();
+        if p.bytes[p.pos..].starts_with(b"//") {
+            p.pos += 2;
+            p.authority(&mut u)?;
+        }
+        u.path = p.path(u.scheme.is_none() && u.authority.is_none())?;
+        if p.peek() == Some(b'?') {
+            p.pos += 1;
+            u.query = Some(p.query()?);
+        }
+        if p.peek() == Some(b'#') {
+            p.pos += 1;
+            u.fragment = Some(p.query()?);
+        }
+        if p.peek().is_some() {
+            return Err(p.unexpected(u.last_production()));
+        }
+
+        // Every byte was checked to be one of the ASCII characters of the grammar.
+        u.source = std::str::from_utf8(bytes).unwrap();
+        Ok(u)
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.scheme.is_some()
+    }
+
+    pub fn scheme(&self) -> Option<&'a str> {
+        self.slice(&self.scheme)
+    }
+
+    pub fn authority(&self) -> Option<&'a str> {
+        self.slice(&self.authority)
+    }
+
+    pub fn userinfo(&self) -> Option<&'a str> {
+        self.slice(&self.userinfo)
+    }
+
+    pub fn host(&self) -> Option<&'a str> {
+        self.slice(&self.host)
+    }
+
+    /// Returns the digits of the port, which are empty for e.g. "http://a:/".
+    pub fn port(&self) -> Option<&'a str> {
+        self.slice(&self.port)
+    }
+
+    pub fn path(&self) -> &'a str {
+        &self.source[self.path.clone()]
+    }
+
+    pub fn query(&self) -> Option<&'a str> {
+        self.slice(&self.query)
+    }
+
+    pub fn fragment(&self) -> Option<&'a str> {
+        self.slice(&self.fragment)
+    }
+
+    /// Returns the owned `Uri` with the same components. The components are not
+    /// parsed again, parsing checked them already.
+    pub fn to_uri(&self) -> Uri {
+        let chars = |s: &str| encoded_chars(s);
+        let authority = self.authority().map(|_| Authority {
+            userinfo: self.userinfo().map(|u| Userinfo(chars(u))),
+            hostport: Hostport(self.to_host(), self.port().map(|p| Port(chars(p)))),
+        });
+        let segments = self
+            .path()
+            .split('/')
+            .map(|s| {
+                let mut params = s.split(';');
+                let pchars = chars(params.next().unwrap_or(""));
+                let params: Vec<Param> = params.map(|p| Param { pchars: chars(p) }).collect();
+                Segment {
+                    pchars,
+                    params: match params.is_empty() {
+                        true => None,
+                        false => Some(params),
+                    },
+                }
+            })
+            .collect();
+        Uri {
+            scheme: self.scheme().map(|s| Scheme(chars(s))),
+            authority,
+            path: PathSegments { segments },
+            query: self.query().map(|q| Query(chars(q))),
+            fragment: self.fragment().map(|f| Fragment(chars(f))),
+        }
+    }
+
+    // Returns the host told apart like `host` does: an IP literal, a reg-name that
+    // is an IPv4address or a hostname, or any other reg-name.
+    fn to_host(&self) -> Host {
+        let h = self.host().unwrap_or("");
+        let ascii = |s: &str| -> Vec<Char> { s.bytes().map(Char::Ascii).collect() };
+        if let Some(literal) = h.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            let il = match self.ipv6 {
+                Some(addr) => {
+                    let (address, zone_id) = match literal.find("%25") {
+                        Some(i) => (&literal[..i], Some(&literal[i + 3..])),
+                        None => (literal, None),
+                    };
+                    IPLiteral::IPv6address(IPv6address {
+                        tokens: ascii(address),
+                        addr,
+                        zone_id: zone_id.map(|z| ZoneId(encoded_chars(z))),
+                    })
+                }
+                None => {
+                    let dot = literal.find('.').unwrap_or(literal.len());
+                    IPLiteral::IPvFuture(IPvFuture(
+                        Char::Ascii(literal.as_bytes()[0]),
+                        ascii(&literal[1..dot]),
+                        ascii(literal.get(dot + 1..).unwrap_or("")),
+                    ))
+                }
+            };
+            return Host::IPLiteral(il);
+        }
+
+        if is_ipv4_address(h) {
+            let octets: Vec<Vec<Char>> = h.split('.').map(ascii).collect();
+            let o = |i: usize| octets[i].clone();
+            return Host::IPv4address(IPv4address(o(0), o(1), o(2), o(3)));
+        }
+
+        let (name, dot) = match h.strip_suffix('.') {
+            Some(name) => (name, Some(Char::Ascii(b'.'))),
+            None => (h, None),
+        };
+        let mut labels: Vec<&str> = name.split('.').collect();
+        let top = labels.pop().unwrap_or("");
+        let is_hostname = labels.iter().all(|l| is_label(l))
+            && is_label(top)
+            && top.as_bytes()[0].is_ascii_alphabetic();
+        match is_hostname {
+            true => Host::Hostname(Hostname(
+                labels.iter().map(|l| Domainlabel(ascii(l))).collect(),
+                Toplabel(ascii(top)),
+                dot,
+            )),
+            false => Host::RegName(RegName(encoded_chars(h))),
+        }
+    }
+
+    // Returns the production that was parsed last, like `last_production` for `Uri`.
+    fn last_production(&self) -> Production {
+        if self.fragment.is_some() {
+            Production::Fragment
+        } else if self.query.is_some() {
+            Production::Query
+        } else if !self.path.is_empty() || self.authority.is_none() {
+            Production::Path
+        } else if self.port.is_some() {
+            Production::Port
+        } else {
+            Production::Host
+        }
+    }
+
+    fn slice(&self, range: &Option<Range<usize>>) -> Option<&'a str> {
+        range.as_ref().map(|r| &self.source[r.clone()])
+    }
+}
+
+impl<'a> std::fmt::Display for UriRef<'a> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str(self.source)
+    }
+}
+
+impl<'a> From<UriRef<'a>> for Uri {
+    fn from(u: UriRef<'a>) -> Uri {
+        u.to_uri()
+    }
+}
+
+impl<'a> From<UriRef<'a>> for &'a str {
+    fn from(u: UriRef<'a>) -> &'a str {
+        u.source
+    }
+}
+
+impl<'a> TryFrom<&'a str> for UriRef<'a> {
+    type Error = UriError;
+
+    fn try_from(s: &'a str) -> Result<UriRef<'a>> {
+        UriRef::parse_str(s)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for UriRef<'a> {
+    type Error = UriError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<UriRef<'a>> {
+        UriRef::parse(bytes)
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).cloned()
+    }
+
+    fn unexpected(&self, production: Production) -> UriError {
+        let kind = match self.peek() {
+            Some(_) => UriErrorKind::UnexpectedByte,
+            None => UriErrorKind::UnexpectedEnd,
+        };
+        UriError::new(kind, production, self.pos, self.peek())
+    }
+
+    fn invalid(&self, production: Production) -> UriError {
+        UriError::new(UriErrorKind::Invalid, production, self.pos, self.peek())
+    }
+
+    // Returns the scheme if the input starts with one followed by ":".
+    fn scheme(&mut self) -> Option<Range<usize>> {
+        match self.peek() {
+            Some(b) if b.is_ascii_alphabetic() => {}
+            _ => return None,
+        }
+        let mut end = 1;
+        while end < self.bytes.len() {
+            let b = self.bytes[end];
+            if !(b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.') {
+                break;
+            }
+            end += 1;
+        }
+        match self.bytes.get(end) {
+            Some(b':') => {
+                self.pos = end + 1;
+                Some(0..end)
+            }
+            _ => None,
+        }
+    }
+
+    fn authority(&mut self, u: &mut UriRef) -> Result<()> {
+        let start = self.pos;
+        let end = match self.bytes[start..]
+            .iter()
+            .position(|b| *b == b'/' || *b == b'?' || *b == b'#')
+        {
+            Some(i) => start + i,
+            None => self.bytes.len(),
+        };
+
+        if let Some(i) = self.bytes[start..end].iter().position(|b| *b == b'@') {
+            u.userinfo = Some(self.scan(start + i, |b| {
+                is_unreserved(b) || is_sub_delim(b) || b == b':'
+            })?);
+            if self.pos != start + i {
+                return Err(self.unexpected(Production::Userinfo));
+            }
+            self.pos += 1;
+        }
+
+        let host_start = self.pos;
+        if self.peek() == Some(b'[') {
+            self.ip_literal(u, end)?;
+        } else {
+            self.scan(end, |b| is_unreserved(b) || is_sub_delim(b))?;
+        }
+        u.host = Some(host_start..self.pos);
+
+        if self.pos < end {
+            if self.bytes[self.pos] != b':' {
+                return Err(self.unexpected(Production::Host));
+            }
+            self.pos += 1;
+            let port_start = self.pos;
+            while self.pos < end {
+                match self.bytes[self.pos].is_ascii_digit() {
+                    true => self.pos += 1,
+                    false => return Err(self.unexpected(Production::Port)),
+                }
+            }
+            let digits = std::str::from_utf8(&self.bytes[port_start..end]).unwrap();
+            if !digits.is_empty() && digits.parse::<u16>().is_err() {
+                self.pos = port_start;
+                return Err(self.invalid(Production::Port));
+            }
+            u.port = Some(port_start..end);
+        }
+        u.authority = Some(start..end);
+        Ok(())
+    }
+
+    // IP-literal = "[" ( IPv6address / IPv6addrz / IPvFuture  ) "]"
+    fn ip_literal(&mut self, u: &mut UriRef, end: usize) -> Result<()> {
+        let close = match self.bytes[self.pos..end].iter().position(|b| *b == b']') {
+            Some(i) => self.pos + i,
+            None => {
+                self.pos = end;
+                return Err(self.unexpected(Production::IpLiteral));
+            }
+        };
+        self.pos += 1;
+        let literal = &self.bytes[self.pos..close];
+        match literal.first() {
+            Some(b'v') | Some(b'V') if !is_ipv_future(&literal[1..]) => {
+                return Err(self.invalid(Production::IpvFuture));
+            }
+            Some(b'v') | Some(b'V') => {}
+            _ => self.ipv6_address(u, close)?,
+        }
+        self.pos = close + 1;
+        Ok(())
+    }
+
+    // IPv6address and IPv6addrz = IPv6address "%25" ZoneID (RFC 6874), up to `close`.
+    fn ipv6_address(&mut self, u: &mut UriRef, close: usize) -> Result<()> {
+        let literal = &self.bytes[self.pos..close];
+        let len = literal
+            .iter()
+            .take_while(|b| b.is_ascii_hexdigit() || **b == b':' || **b == b'.')
+            .count();
+        let address = std::str::from_utf8(&literal[..len]).unwrap();
+        match address.parse::<Ipv6Addr>() {
+            Ok(addr) => u.ipv6 = Some(addr),
+            Err(_) => {
+                if let Some(i) = invalid_ls32(address) {
+                    self.pos += i;
+                    return Err(self.invalid(Production::Ipv4Address));
+                }
+                return Err(match literal.first() {
+                    Some(b) if b.is_ascii_hexdigit() || *b == b':' => {
+                        self.invalid(Production::Ipv6Address)
+                    }
+                    _ => self.unexpected(Production::IpLiteral),
+                });
+            }
+        }
+        self.pos += len;
+        if self.bytes[self.pos..close].starts_with(b"%25") {
+            self.pos += 3;
+            if self.scan(close, is_unreserved)?.is_empty() {
+                return Err(self.unexpected(Production::ZoneId));
+            }
+        }
+        match self.pos == close {
+            true => Ok(()),
+            false => Err(self.unexpected(Production::IpLiteral)),
+        }
+    }
+
+    // Scans characters that are allowed by `is_allowed` or percent-encoded, up to
+    // `end` or the first character that is not.
+    fn scan<F>(&mut self, end: usize, is_allowed: F) -> Result<Range<usize>>
+    where
+        F: Fn(u8) -> bool,
+    {
+        let start = self.pos;
+        while self.pos < end {
+            let b = self.bytes[self.pos];
+            if b == b'%' {
+                self.escape()?;
+            } else if is_allowed(b) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(start..self.pos)
+    }
+
+    fn escape(&mut self) -> Result<()> {
+        for _ in 0..2 {
+            self.pos += 1;
+            match self.peek() {
+                Some(h) if h.is_ascii_hexdigit() => {}
+                _ => return Err(self.unexpected(Production::Escape)),
+            }
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // path-abempty / path-absolute / path-noscheme / path-rootless / path-empty
+    fn path(&mut self, no_scheme: bool) -> Result<Range<usize>> {
+        let start = self.pos;
+        let len = self.bytes.len();
+        let range = self.scan(len, |b| is_pchar(b) || b == b'/')?;
+        if no_scheme {
+            let first_segment = self.bytes[range.clone()].split(|b| *b == b'/').next();
+            if let Some(i) = first_segment.and_then(|s| s.iter().position(|b| *b == b':')) {
+                self.pos = start + i;
+                return Err(self.unexpected(Production::Path));
+            }
+        }
+        Ok(range)
+    }
+
+    // query = *( pchar / "/" / "?" ), and the same for fragment
+    fn query(&mut self) -> Result<Range<usize>> {
+        let len = self.bytes.len();
+        self.scan(len, |b| is_pchar(b) || b == b'/' || b == b'?')
+    }
+}
+
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_' || b == b'~'
+}
+
+fn is_sub_delim(b: u8) -> bool {
+    b"!$&'()*+,;=".contains(&b)
+}
+
+fn is_pchar(b: u8) -> bool {
+    is_unreserved(b) || is_sub_delim(b) || b == b':' || b == b'@'
+}
+
+// Returns the offset of the IPv4address an IPv6address ends with, ls32 = ( h16 ":" h16 )
+// / IPv4address, if it is not one.
+pub(crate) fn invalid_ls32(address: &str) -> Option<usize> {
+    let start = address[..address.find('.')?].rfind(':')? + 1;
+    match is_ipv4_address(&address[start..]) {
+        true => None,
+        false => Some(start),
+    }
+}
+
+// IPv4address = dec-octet "." dec-octet "." dec-octet "." dec-octet
+fn is_ipv4_address(s: &str) -> bool {
+    let octets: Vec<&str> = s.split('.').collect();
+    octets.len() == 4 && octets.iter().all(|o| is_dec_octet(o))
+}
+
+// dec-octet = "0" / %x31-39 DIGIT / "1" 2DIGIT / "2" %x30-34 DIGIT / "25" %x30-35
+fn is_dec_octet(s: &str) -> bool {
+    let b = s.as_bytes();
+    match b.len() {
+        1 => b[0].is_ascii_digit(),
+        2 | 3 => b[0] != b'0' && b.iter().all(u8::is_ascii_digit) && s.parse::<u8>().is_ok(),
+        _ => false,
+    }
+}
+
+// hostname labels are alphanumerics and "-", starting and ending with an alphanumeric
+fn is_label(s: &str) -> bool {
+    let b = s.as_bytes();
+    !b.is_empty()
+        && b[0].is_ascii_alphanumeric()
+        && b[b.len() - 1].is_ascii_alphanumeric()
+        && b.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'-')
+}
+
+// IPvFuture without the leading "v": 1*HEXDIG "." 1*( unreserved / sub-delims / ":" )
+fn is_ipv_future(literal: &[u8]) -> bool {
+    let dot = match literal.iter().position(|b| *b == b'.') {
+        Some(i) => i,
+        None => return false,
+    };
+    let (version, rest) = (&literal[..dot], &literal[dot + 1..]);
+    !version.is_empty()
+        && version.iter().all(|b| b.is_ascii_hexdigit())
+        && !rest.is_empty()
+        && rest
+            .iter()
+            .all(|b| is_unreserved(*b) || is_sub_delim(*b) || *b == b':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::parse_complete;
+    use uri::token_buffer::TokenBuffer;
+
+    // Parses with the grammar that builds the `Uri` character by character.
+    fn parse_chars(bytes: &[u8]) -> Result<Uri> {
+        parse_complete(&mut TokenBuffer::from(bytes)).map_err(|e| e.with_input(bytes))
+    }
+
+    #[test]
+    fn test_components() -> Result<()> {
+        let s = "http://user:pw@www.example.com:8080/a/b;p=1?q=1&r#frag";
+        let u = UriRef::parse(s.as_bytes())?;
+        assert_eq!(s, u.as_str());
+        assert_eq!(Some("http"), u.scheme());
+        assert_eq!(Some("user:pw@www.example.com:8080"), u.authority());
+        assert_eq!(Some("user:pw"), u.userinfo());
+        assert_eq!(Some("www.example.com"), u.host());
+        assert_eq!(Some("8080"), u.port());
+        assert_eq!("/a/b;p=1", u.path());
+        assert_eq!(Some("q=1&r"), u.query());
+        assert_eq!(Some("frag"), u.fragment());
+
+        let u = UriRef::parse(b"mailto:a@b.com")?;
+        assert_eq!(Some("mailto"), u.scheme());
+        assert_eq!(None, u.authority());
+        assert_eq!("a@b.com", u.path());
+
+        let u = UriRef::parse(b"//[fe80::1%25en0]:/x")?;
+        assert_eq!(None, u.scheme());
+        assert_eq!(Some("[fe80::1%25en0]"), u.host());
+        assert_eq!(Some(""), u.port());
+
+        let u = UriRef::parse(b"")?;
+        assert_eq!("", u.path());
+        assert_eq!(None, u.query());
+
+        let s = "http://a/b?c";
+        let u = UriRef::try_from(s)?;
+        assert_eq!(UriRef::parse_str(s)?, u);
+        assert_eq!(s, <&str>::from(u));
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_as_uri_parse() -> Result<()> {
+        let valid = [
+            "ftp://ftp.is.co.za/rfc/rfc1808.txt",
+            "http://www.ietf.org/rfc/rfc2396.txt",
+            "ldap://[2001:db8::7]/c=GB?objectClass?one",
+            "mailto:John.Doe@example.com",
+            "news:comp.infosystems.www.servers.unix",
+            "tel:+1-816-555-1212",
+            "telnet://192.0.2.16:80/",
+            "urn:oasis:names:specification:docbook:dtd:xml:4.1.2",
+            "foo://example.com:8042/over/there?name=ferret#nose",
+            "http://a/b/c/d;p?q",
+            "http://[v7.fe80::a+en1]/",
+            "http://%31%32%37.0.0.1/",
+            "file:///etc/hosts",
+            "./this:that",
+            "//g",
+            "?y",
+            "#s",
+            "g;x=1/../y;;a;/",
+            "",
+            "/a//b/",
+            "http://a/%7Euser%20name/%e2%82%ac?%3D#%2F",
+            "s://!$&'()*+,;=:@x/!$&'()*+,;=:@?/?#/?",
+        ];
+        for s in valid.iter() {
+            let u = UriRef::parse(s.as_bytes())?;
+            assert_eq!(parse_chars(s.as_bytes())?, u.to_uri(), "{}", s);
+            assert_eq!(*s, Uri::from(u).to_string());
+        }
+
+        let invalid = [
+            "http://foo/bar baz",
+            "http://foo/bar#a#b",
+            "{foo}",
+            "1a:b",
+            "http://[::1/",
+            "http://[]/",
+            "http://[1::2::3]/",
+            "http://[v.x]/",
+            "http://[::1]x/",
+            "http://a:8x/",
+            "http://a@b@c/",
+            "http://a b@c/",
+            "http://a/%zz",
+            "http://a/%2",
+            "http://a:65536/",
+            "http://a:99999999999999999999/",
+            "a/ä",
+        ];
+        for s in invalid.iter() {
+            assert!(parse_chars(s.as_bytes()).is_err(), "{}", s);
+            assert!(UriRef::parse(s.as_bytes()).is_err(), "{}", s);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = UriRef::parse(b"http://foo/bar baz").unwrap_err();
+        assert_eq!(
+            "Unexpected character ' ' in path at byte 14.\nhttp://foo/bar baz\n              ^",
+            e.to_string()
+        );
+
+        let inputs = [
+            "http://exa mple.com/",
+            "http://host:80x/",
+            "http://[::1/",
+            "http://[]/",
+            "http://[1::2::3]/",
+            "http://[::ffff:1.2.3.256]/",
+            "http://[fe80::1%25]/",
+            "http://[fe80::1%25en0!]/",
+            "http://[::1x]/",
+            "http://[.1]/",
+            "http://[v1]/",
+            "http://[::1]x/",
+            "http://a/%zz",
+            "http://a/%2",
+            "http://a:65536/",
+            "http://a:99999999999999999999/",
+            "http://a/?q=^",
+            "http://a/#a#b",
+            "1a:b",
+            "a/\u{e4}",
+        ];
+        for s in inputs.iter() {
+            let expected = parse_chars(s.as_bytes()).unwrap_err();
+            let actual = UriRef::parse(s.as_bytes()).unwrap_err();
+            assert_eq!(expected, actual, "{}", s);
+        }
+    }
+}
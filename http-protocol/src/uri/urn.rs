@@ -0,0 +1,323 @@
+//https://tools.ietf.org/html/rfc8141
+
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::Uri;
+
+/// A "urn:" URI, split into its namespace identifier (NID), namespace specific
+/// string (NSS) and components.
+///
+/// ```text
+/// namestring    = assigned-name [ rq-components ] [ "#" f-component ]
+/// assigned-name = "urn" ":" NID ":" NSS
+/// rq-components = [ "?+" r-component ] [ "?=" q-component ]
+/// ```
+///
+/// URNs compare by lexical equivalence: "urn" and the NID are case-insensitive,
+/// as are the hex digits of escapes in the NSS, and the components are ignored.
+#[derive(Clone, Debug)]
+pub struct Urn {
+    nid: String,
+    nss: String,
+    r_component: Option<String>,
+    q_component: Option<String>,
+    f_component: Option<String>,
+}
+
+impl Urn {
+    /// Returns the view of a URI with the scheme "urn". Error offsets are the ones in
+    /// the URI.
+    pub fn from_uri(uri: &Uri) -> Result<Urn> {
+        let (opaque_part, offset) = uri.opaque_part_for("urn")?;
+        let mut urn = parse_opaque_part(&opaque_part)
+            .map_err(|e| e.shifted(offset).with_input(uri.to_string().as_bytes()))?;
+        urn.f_component = uri.fragment.as_ref().map(|f| f.to_string());
+        Ok(urn)
+    }
+
+    /// Returns the NID as written, e.g. "ISBN".
+    pub fn nid(&self) -> &str {
+        &self.nid
+    }
+
+    /// Returns the NSS as written, with its escapes.
+    pub fn nss(&self) -> &str {
+        &self.nss
+    }
+
+    /// Returns the parameters for the resolver of the URN.
+    pub fn r_component(&self) -> Option<&str> {
+        self.r_component.as_deref()
+    }
+
+    /// Returns the parameters for the named resource.
+    pub fn q_component(&self) -> Option<&str> {
+        self.q_component.as_deref()
+    }
+
+    pub fn f_component(&self) -> Option<&str> {
+        self.f_component.as_deref()
+    }
+
+    /// Returns the assigned name in the normal form used for lexical equivalence,
+    /// e.g. "urn:example:a%2Fb" for "URN:Example:a%2fb?=x".
+    pub fn normalized(&self) -> String {
+        let mut s = String::with_capacity(self.nid.len() + self.nss.len() + 5);
+        s.push_str("urn:");
+        s.push_str(&self.nid.to_ascii_lowercase());
+        s.push(':');
+        let mut escape = 0;
+        for c in self.nss.chars() {
+            match c {
+                '%' => escape = 2,
+                _ if escape > 0 => escape -= 1,
+                _ => {
+                    s.push(c);
+                    continue;
+                }
+            }
+            s.push(c.to_ascii_uppercase());
+        }
+        s
+    }
+}
+
+impl PartialEq for Urn {
+    fn eq(&self, other: &Urn) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for Urn {}
+
+impl Hash for Urn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+impl std::fmt::Display for Urn {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "urn:{}:{}", self.nid, self.nss)?;
+        if let Some(r) = &self.r_component {
+            write!(fmt, "?+{}", r)?;
+        }
+        if let Some(q) = &self.q_component {
+            write!(fmt, "?={}", q)?;
+        }
+        if let Some(f) = &self.f_component {
+            write!(fmt, "#{}", f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Urn {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<Urn> {
+        Urn::from_uri(&Uri::parse(s.as_bytes())?)
+    }
+}
+
+// Parses what follows "urn:", without the f-component. Error offsets are the ones in
+// it.
+fn parse_opaque_part(opaque_part: &str) -> Result<Urn> {
+    let c = |i: usize| opaque_part.as_bytes().get(i).map(|b| Char::Ascii(*b));
+    let (assigned_name, mut rq_components) = match opaque_part.find('?') {
+        Some(i) => opaque_part.split_at(i),
+        None => (opaque_part, ""),
+    };
+    let (nid, nss) = match assigned_name.find(':') {
+        Some(i) => (&assigned_name[..i], &assigned_name[i + 1..]),
+        None => {
+            let end = assigned_name.len();
+            return Err(UriError::unexpected(Production::Nid, end, c(end)));
+        }
+    };
+    if !is_nid(nid) {
+        return Err(UriError::invalid(Production::Nid, 0, c(0)));
+    }
+    if nss.is_empty() || nss.starts_with('/') {
+        let start = nid.len() + 1;
+        return Err(UriError::unexpected(Production::Nss, start, c(start)));
+    }
+
+    let mut start = assigned_name.len();
+    let mut r_component: Option<String> = None;
+    let mut q_component: Option<String> = None;
+    if let Some(rest) = rq_components.strip_prefix("?+") {
+        let end = rest.find("?=").unwrap_or(rest.len());
+        if end == 0 {
+            return Err(UriError::unexpected(
+                Production::RComponent,
+                start + 2,
+                c(start + 2),
+            ));
+        }
+        r_component = Some(rest[..end].to_string());
+        rq_components = &rest[end..];
+        start += 2 + end;
+    }
+    if let Some(rest) = rq_components.strip_prefix("?=") {
+        if rest.is_empty() {
+            return Err(UriError::unexpected(
+                Production::QComponent,
+                start + 2,
+                None,
+            ));
+        }
+        q_component = Some(rest.to_string());
+        rq_components = "";
+    }
+    if !rq_components.is_empty() {
+        return Err(UriError::unexpected(
+            Production::Query,
+            start + 1,
+            c(start + 1),
+        ));
+    }
+
+    Ok(Urn {
+        nid: nid.to_string(),
+        nss: nss.to_string(),
+        r_component,
+        q_component,
+        f_component: None,
+    })
+}
+
+// NID = (alphanum) 0*30(ldh) (alphanum)
+fn is_nid(nid: &str) -> bool {
+    let bytes = nid.as_bytes();
+    (2..=32).contains(&bytes.len())
+        && bytes[0].is_ascii_alphanumeric()
+        && bytes[bytes.len() - 1].is_ascii_alphanumeric()
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uri::error::UriErrorKind;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let u: Urn = "urn:ISBN:0-395-36341-1".parse()?;
+        assert_eq!("ISBN", u.nid());
+        assert_eq!("0-395-36341-1", u.nss());
+        assert_eq!(None, u.r_component());
+
+        let u: Urn = "urn:example:weather?+CCResolve:cc=uk?=op=map&lat=39.56#top".parse()?;
+        assert_eq!("weather", u.nss());
+        assert_eq!(Some("CCResolve:cc=uk"), u.r_component());
+        assert_eq!(Some("op=map&lat=39.56"), u.q_component());
+        assert_eq!(Some("top"), u.f_component());
+        assert_eq!(
+            "urn:example:weather?+CCResolve:cc=uk?=op=map&lat=39.56#top",
+            u.to_string()
+        );
+
+        let u: Urn = "urn:example:a?=b?+c".parse()?;
+        assert_eq!(None, u.r_component());
+        assert_eq!(Some("b?+c"), u.q_component());
+
+        let u: Urn = "urn:example:foo-bar-baz-qux?+CCResolve:cc=uk".parse()?;
+        assert_eq!(Some("CCResolve:cc=uk"), u.r_component());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lexical_equivalence() -> Result<()> {
+        let equivalent = [
+            "urn:example:a123,z456",
+            "URN:example:a123,z456",
+            "urn:EXAMPLE:a123,z456",
+            "urn:example:a123,z456?+abc",
+            "urn:example:a123,z456?=xyz",
+            "urn:example:a123,z456#789",
+        ];
+        let first: Urn = equivalent[0].parse()?;
+        for e in equivalent.iter() {
+            assert_eq!(first, e.parse::<Urn>()?, "{}", e);
+        }
+
+        let different = [
+            "urn:example:a123,z456/foo",
+            "urn:example:a123,z456/bar",
+            "urn:example:A123,z456",
+            "urn:example:a123%2Cz456",
+        ];
+        for d in different.iter() {
+            assert!(first != d.parse::<Urn>()?, "{}", d);
+        }
+
+        let u: Urn = "URN:Example:a%2fb%c3%a4x".parse()?;
+        assert_eq!("urn:example:a%2Fb%C3%A4x", u.normalized());
+        assert_eq!(u, "urn:example:a%2Fb%C3%A4x".parse()?);
+        Ok(())
+    }
+
+    fn parse_error(input: &str) -> (UriErrorKind, Production, usize) {
+        let e = input.parse::<Urn>().unwrap_err();
+        (e.kind(), e.production(), e.offset())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use uri::error::UriErrorKind::*;
+
+        assert_eq!(
+            (NotAllowed, Production::Scheme, 0),
+            parse_error("http://a/")
+        );
+        assert_eq!(
+            (UnexpectedEnd, Production::Nid, 11),
+            parse_error("urn:example")
+        );
+        assert_eq!(
+            (UnexpectedEnd, Production::Nss, 12),
+            parse_error("urn:example:")
+        );
+        assert_eq!((Invalid, Production::Nid, 4), parse_error("urn:a:b"));
+        assert_eq!((Invalid, Production::Nid, 4), parse_error("urn:-example:b"));
+        assert_eq!((Invalid, Production::Nid, 4), parse_error("urn:example-:b"));
+        assert_eq!((Invalid, Production::Nid, 4), parse_error("urn:exa_mple:b"));
+        assert_eq!(
+            (Invalid, Production::Nid, 4),
+            parse_error("urn:123456789012345678901234567890123:b")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Nss, 12),
+            parse_error("urn:example:/b")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::Query, 14),
+            parse_error("urn:example:a?b")
+        );
+        assert_eq!(
+            (UnexpectedEnd, Production::RComponent, 15),
+            parse_error("urn:example:a?+")
+        );
+        assert_eq!(
+            (UnexpectedByte, Production::RComponent, 15),
+            parse_error("urn:example:a?+?=b")
+        );
+        assert_eq!(
+            (UnexpectedEnd, Production::QComponent, 15),
+            parse_error("urn:example:a?=")
+        );
+        assert_eq!(
+            "Invalid NID at byte 4.\nurn:a:b\n    ^",
+            "urn:a:b".parse::<Urn>().unwrap_err().to_string()
+        );
+    }
+}
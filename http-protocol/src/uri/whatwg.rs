@@ -0,0 +1,1069 @@
+//https://url.spec.whatwg.org/#url-parsing
+
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use uri::char_stream::Char;
+use uri::error::Production;
+use uri::error::Result;
+use uri::error::UriError;
+use uri::host;
+use uri::percent::encoded_chars;
+use uri::percent_decode;
+use uri::token_buffer::TokenBuffer;
+use uri::Authority;
+use uri::Fragment;
+use uri::Host;
+use uri::Hostport;
+use uri::Param;
+use uri::PathSegments;
+use uri::Port;
+use uri::Query;
+use uri::RegName;
+use uri::Scheme;
+use uri::Segment;
+use uri::Uri;
+use uri::Userinfo;
+
+impl Uri {
+    /// Parses `input` like browsers do, following the WHATWG URL Standard instead of
+    /// RFC 3986: tabs and newlines are removed, "\" is a "/" in special schemes like
+    /// "http", hosts are IDNA-mapped and IPv4 shorthands like "0x7f.1" are expanded,
+    /// and characters that are not allowed are percent-encoded instead of rejected.
+    /// A relative `input` is resolved against `base`.
+    ///
+    /// The result serializes to the URL's "href". It is not always a valid RFC 3986
+    /// URI, as the standard leaves characters like "|" or "^" unencoded.
+    pub fn parse_whatwg(input: &str, base: Option<&Uri>) -> Result<Uri> {
+        let base = match base {
+            Some(b) => {
+                let b = b.to_string();
+                Some(
+                    Parser::new(&b)
+                        .parse(None)
+                        .map_err(|e| e.with_input(b.as_bytes()))?,
+                )
+            }
+            None => None,
+        };
+        let url = Parser::new(input)
+            .parse(base.as_ref())
+            .map_err(|e| e.with_input(input.as_bytes()))?;
+        Ok(url.to_uri())
+    }
+}
+
+// The URL record of the standard, with hosts already serialized.
+#[derive(Clone, Debug, Default)]
+struct Url {
+    scheme: String,
+    username: String,
+    password: String,
+    host: Option<String>,
+    port: Option<u16>,
+    path: Vec<String>,
+    opaque_path: Option<String>,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+impl Url {
+    fn is_special(&self) -> bool {
+        is_special(&self.scheme)
+    }
+
+    fn shorten_path(&mut self) {
+        if self.scheme == "file"
+            && self.path.len() == 1
+            && is_normalized_windows_drive_letter(&self.path[0])
+        {
+            return;
+        }
+        self.path.pop();
+    }
+
+    fn to_uri(&self) -> Uri {
+        let authority = self.host.as_ref().map(|h| Authority {
+            userinfo: match (self.username.is_empty(), self.password.is_empty()) {
+                (true, true) => None,
+                (_, true) => Some(Userinfo(encoded_chars(&self.username))),
+                _ => Some(Userinfo(encoded_chars(&format!(
+                    "{}:{}",
+                    self.username, self.password
+                )))),
+            },
+            hostport: Hostport(
+                to_host(h),
+                self.port.map(|p| Port(encoded_chars(&p.to_string()))),
+            ),
+        });
+
+        let mut path = String::new();
+        match &self.opaque_path {
+            Some(p) => path.push_str(p),
+            None => {
+                if self.host.is_none() && self.path.len() > 1 && self.path[0].is_empty() {
+                    path.push_str("/.");
+                }
+                for s in &self.path {
+                    path.push('/');
+                    path.push_str(s);
+                }
+            }
+        }
+        let segments = path
+            .split('/')
+            .map(|s| {
+                let mut params = s.split(';');
+                let pchars = encoded_chars(params.next().unwrap_or(""));
+                let params: Vec<Param> = params
+                    .map(|p| Param {
+                        pchars: encoded_chars(p),
+                    })
+                    .collect();
+                Segment {
+                    pchars,
+                    params: match params.is_empty() {
+                        true => None,
+                        false => Some(params),
+                    },
+                }
+            })
+            .collect();
+
+        Uri {
+            scheme: Some(Scheme(encoded_chars(&self.scheme))),
+            authority,
+            path: PathSegments { segments },
+            query: self.query.as_ref().map(|q| Query(encoded_chars(q))),
+            fragment: self.fragment.as_ref().map(|f| Fragment(encoded_chars(f))),
+        }
+    }
+}
+
+// Classifies a serialized host like `Uri::parse` does, falling back to a plain RegName
+// for opaque hosts with characters RFC 3986 does not allow.
+fn to_host(h: &str) -> Host {
+    let mut tb = TokenBuffer::from(encoded_chars(h));
+    match host(&mut tb) {
+        Ok(host) if tb.pop().ok() == Some(None) => host,
+        _ => Host::RegName(RegName(encoded_chars(h))),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    SchemeStart,
+    Scheme,
+    NoScheme,
+    SpecialRelativeOrAuthority,
+    PathOrAuthority,
+    Relative,
+    RelativeSlash,
+    SpecialAuthoritySlashes,
+    SpecialAuthorityIgnoreSlashes,
+    Authority,
+    Host,
+    Port,
+    File,
+    FileSlash,
+    FileHost,
+    PathStart,
+    Path,
+    OpaquePath,
+    Query,
+    Fragment,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    // The code points without leading and trailing C0 controls and spaces and without
+    // tabs and newlines, with their offsets in the input.
+    chars: Vec<(usize, char)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Parser<'a> {
+        let trimmed = input.trim_matches(|c: char| c <= ' ');
+        let start = input.len() - input.trim_start_matches(|c: char| c <= ' ').len();
+        let chars = trimmed
+            .char_indices()
+            .filter(|(_, c)| !['\t', '\n', '\r'].contains(c))
+            .map(|(i, c)| (start + i, c))
+            .collect();
+        Parser { input, chars }
+    }
+
+    fn offset(&self, p: isize) -> usize {
+        match self.chars.get(p as usize) {
+            Some((i, _)) if p >= 0 => *i,
+            _ => self.input.len(),
+        }
+    }
+
+    fn char_at(&self, p: isize) -> Option<char> {
+        match p >= 0 {
+            true => self.chars.get(p as usize).map(|(_, c)| *c),
+            false => None,
+        }
+    }
+
+    fn remaining_starts_with(&self, p: isize, c: char) -> bool {
+        self.char_at(p + 1) == Some(c)
+    }
+
+    fn error(&self, production: Production, p: isize) -> UriError {
+        let offset = self.offset(p);
+        let c = self.input.as_bytes().get(offset).map(|b| Char::Ascii(*b));
+        match c {
+            Some(_) => UriError::invalid(production, offset, c),
+            None => UriError::unexpected(production, offset, None),
+        }
+    }
+
+    fn starts_with_windows_drive_letter(&self, p: isize) -> bool {
+        let c = |i: isize| self.char_at(p + i);
+        match (c(0), c(1), c(2)) {
+            (Some(a), Some(b), next) => {
+                a.is_ascii_alphabetic()
+                    && (b == ':' || b == '|')
+                    && next.is_none_or(|n| ['/', '\\', '?', '#'].contains(&n))
+            }
+            _ => false,
+        }
+    }
+
+    fn parse(&self, base: Option<&Url>) -> Result<Url> {
+        let mut url = Url::default();
+        let mut state = State::SchemeStart;
+        let mut buffer = String::new();
+        let mut at_sign_seen = false;
+        let mut inside_brackets = false;
+        let mut password_token_seen = false;
+        let mut p: isize = 0;
+
+        loop {
+            let c = self.char_at(p);
+            match state {
+                State::SchemeStart => match c {
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        buffer.push(c.to_ascii_lowercase());
+                        state = State::Scheme;
+                    }
+                    _ => {
+                        state = State::NoScheme;
+                        p -= 1;
+                    }
+                },
+                State::Scheme => match c {
+                    Some(c) if c.is_ascii_alphanumeric() || "+-.".contains(c) => {
+                        buffer.push(c.to_ascii_lowercase());
+                    }
+                    Some(':') => {
+                        url.scheme = std::mem::take(&mut buffer);
+                        if url.scheme == "file" {
+                            state = State::File;
+                        } else if url.is_special() && base.is_some_and(|b| b.scheme == url.scheme) {
+                            state = State::SpecialRelativeOrAuthority;
+                        } else if url.is_special() {
+                            state = State::SpecialAuthoritySlashes;
+                        } else if self.remaining_starts_with(p, '/') {
+                            state = State::PathOrAuthority;
+                            p += 1;
+                        } else {
+                            url.opaque_path = Some(String::new());
+                            state = State::OpaquePath;
+                        }
+                    }
+                    _ => {
+                        buffer.clear();
+                        state = State::NoScheme;
+                        p = -1;
+                    }
+                },
+                State::NoScheme => {
+                    let base = match base {
+                        Some(b) if b.opaque_path.is_none() || c == Some('#') => b,
+                        _ => return Err(self.error(Production::Scheme, 0)),
+                    };
+                    if base.opaque_path.is_some() {
+                        url.scheme = base.scheme.clone();
+                        url.opaque_path = base.opaque_path.clone();
+                        url.query = base.query.clone();
+                        url.fragment = Some(String::new());
+                        state = State::Fragment;
+                    } else if base.scheme != "file" {
+                        state = State::Relative;
+                        p -= 1;
+                    } else {
+                        state = State::File;
+                        p -= 1;
+                    }
+                }
+                State::SpecialRelativeOrAuthority => {
+                    if c == Some('/') && self.remaining_starts_with(p, '/') {
+                        state = State::SpecialAuthorityIgnoreSlashes;
+                        p += 1;
+                    } else {
+                        state = State::Relative;
+                        p -= 1;
+                    }
+                }
+                State::PathOrAuthority => {
+                    if c == Some('/') {
+                        state = State::Authority;
+                    } else {
+                        state = State::Path;
+                        p -= 1;
+                    }
+                }
+                State::Relative => {
+                    let base = base.expect("relative URLs have a base");
+                    url.scheme = base.scheme.clone();
+                    if c == Some('/') || (url.is_special() && c == Some('\\')) {
+                        state = State::RelativeSlash;
+                    } else {
+                        url.username = base.username.clone();
+                        url.password = base.password.clone();
+                        url.host = base.host.clone();
+                        url.port = base.port;
+                        url.path = base.path.clone();
+                        url.query = base.query.clone();
+                        match c {
+                            Some('?') => {
+                                url.query = Some(String::new());
+                                state = State::Query;
+                            }
+                            Some('#') => {
+                                url.fragment = Some(String::new());
+                                state = State::Fragment;
+                            }
+                            Some(_) => {
+                                url.query = None;
+                                url.shorten_path();
+                                state = State::Path;
+                                p -= 1;
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                State::RelativeSlash => {
+                    if url.is_special() && (c == Some('/') || c == Some('\\')) {
+                        state = State::SpecialAuthorityIgnoreSlashes;
+                    } else if c == Some('/') {
+                        state = State::Authority;
+                    } else {
+                        let base = base.expect("relative URLs have a base");
+                        url.username = base.username.clone();
+                        url.password = base.password.clone();
+                        url.host = base.host.clone();
+                        url.port = base.port;
+                        state = State::Path;
+                        p -= 1;
+                    }
+                }
+                State::SpecialAuthoritySlashes => {
+                    state = State::SpecialAuthorityIgnoreSlashes;
+                    if c == Some('/') && self.remaining_starts_with(p, '/') {
+                        p += 1;
+                    } else {
+                        p -= 1;
+                    }
+                }
+                State::SpecialAuthorityIgnoreSlashes => {
+                    if c != Some('/') && c != Some('\\') {
+                        state = State::Authority;
+                        p -= 1;
+                    }
+                }
+                State::Authority => {
+                    let special_slash = url.is_special() && c == Some('\\');
+                    if c == Some('@') {
+                        if at_sign_seen {
+                            buffer.insert_str(0, "%40");
+                        }
+                        at_sign_seen = true;
+                        for bc in buffer.chars() {
+                            if bc == ':' && !password_token_seen {
+                                password_token_seen = true;
+                                continue;
+                            }
+                            match password_token_seen {
+                                true => encode(&mut url.password, bc, EncodeSet::Userinfo),
+                                false => encode(&mut url.username, bc, EncodeSet::Userinfo),
+                            }
+                        }
+                        buffer.clear();
+                    } else if is_authority_end(c) || special_slash {
+                        if at_sign_seen && buffer.is_empty() {
+                            return Err(self.error(Production::Host, p));
+                        }
+                        p -= buffer.chars().count() as isize + 1;
+                        buffer.clear();
+                        state = State::Host;
+                    } else if let Some(c) = c {
+                        buffer.push(c);
+                    }
+                }
+                State::Host => {
+                    let special_slash = url.is_special() && c == Some('\\');
+                    let start = p - buffer.chars().count() as isize;
+                    if c == Some(':') && !inside_brackets {
+                        if buffer.is_empty() {
+                            return Err(self.error(Production::Host, p));
+                        }
+                        url.host = Some(self.host(&buffer, !url.is_special(), start)?);
+                        buffer.clear();
+                        state = State::Port;
+                    } else if is_authority_end(c) || special_slash {
+                        p -= 1;
+                        if url.is_special() && buffer.is_empty() {
+                            return Err(self.error(Production::Host, start));
+                        }
+                        url.host = Some(self.host(&buffer, !url.is_special(), start)?);
+                        buffer.clear();
+                        state = State::PathStart;
+                    } else if let Some(c) = c {
+                        if c == '[' {
+                            inside_brackets = true;
+                        } else if c == ']' {
+                            inside_brackets = false;
+                        }
+                        buffer.push(c);
+                    }
+                }
+                State::Port => {
+                    let special_slash = url.is_special() && c == Some('\\');
+                    if let Some(d) = c.filter(|c| c.is_ascii_digit()) {
+                        buffer.push(d);
+                    } else if is_authority_end(c) || special_slash {
+                        if !buffer.is_empty() {
+                            let port = match buffer.parse::<u16>() {
+                                Ok(port) => port,
+                                Err(_) => {
+                                    let start = p - buffer.len() as isize;
+                                    return Err(self.error(Production::Port, start));
+                                }
+                            };
+                            url.port = match special_port(&url.scheme) == Some(port) {
+                                true => None,
+                                false => Some(port),
+                            };
+                            buffer.clear();
+                        }
+                        state = State::PathStart;
+                        p -= 1;
+                    } else {
+                        return Err(self.error(Production::Port, p));
+                    }
+                }
+                State::File => {
+                    url.scheme = "file".to_string();
+                    url.host = Some(String::new());
+                    if c == Some('/') || c == Some('\\') {
+                        state = State::FileSlash;
+                    } else if let Some(base) = base.filter(|b| b.scheme == "file") {
+                        url.host = base.host.clone();
+                        url.path = base.path.clone();
+                        url.query = base.query.clone();
+                        match c {
+                            Some('?') => {
+                                url.query = Some(String::new());
+                                state = State::Query;
+                            }
+                            Some('#') => {
+                                url.fragment = Some(String::new());
+                                state = State::Fragment;
+                            }
+                            Some(_) => {
+                                url.query = None;
+                                match self.starts_with_windows_drive_letter(p) {
+                                    true => url.path.clear(),
+                                    false => url.shorten_path(),
+                                }
+                                state = State::Path;
+                                p -= 1;
+                            }
+                            None => {}
+                        }
+                    } else {
+                        state = State::Path;
+                        p -= 1;
+                    }
+                }
+                State::FileSlash => {
+                    if c == Some('/') || c == Some('\\') {
+                        state = State::FileHost;
+                    } else {
+                        if let Some(base) = base.filter(|b| b.scheme == "file") {
+                            url.host = base.host.clone();
+                            if !self.starts_with_windows_drive_letter(p)
+                                && base
+                                    .path
+                                    .first()
+                                    .is_some_and(|s| is_normalized_windows_drive_letter(s))
+                            {
+                                url.path.push(base.path[0].clone());
+                            }
+                        }
+                        state = State::Path;
+                        p -= 1;
+                    }
+                }
+                State::FileHost => match c {
+                    None | Some('/') | Some('\\') | Some('?') | Some('#') => {
+                        let start = p - buffer.chars().count() as isize;
+                        p -= 1;
+                        if is_windows_drive_letter(&buffer) {
+                            state = State::Path;
+                        } else if buffer.is_empty() {
+                            url.host = Some(String::new());
+                            state = State::PathStart;
+                        } else {
+                            let mut host = self.host(&buffer, false, start)?;
+                            if host == "localhost" {
+                                host.clear();
+                            }
+                            url.host = Some(host);
+                            buffer.clear();
+                            state = State::PathStart;
+                        }
+                    }
+                    Some(c) => buffer.push(c),
+                },
+                State::PathStart => {
+                    if url.is_special() {
+                        state = State::Path;
+                        if c != Some('/') && c != Some('\\') {
+                            p -= 1;
+                        }
+                    } else if c == Some('?') {
+                        url.query = Some(String::new());
+                        state = State::Query;
+                    } else if c == Some('#') {
+                        url.fragment = Some(String::new());
+                        state = State::Fragment;
+                    } else if c.is_some() {
+                        state = State::Path;
+                        if c != Some('/') {
+                            p -= 1;
+                        }
+                    }
+                }
+                State::Path => {
+                    let slash = c == Some('/') || (url.is_special() && c == Some('\\'));
+                    if c.is_none() || slash || c == Some('?') || c == Some('#') {
+                        if is_double_dot_segment(&buffer) {
+                            url.shorten_path();
+                            if !slash {
+                                url.path.push(String::new());
+                            }
+                        } else if is_single_dot_segment(&buffer) && !slash {
+                            url.path.push(String::new());
+                        } else if !is_single_dot_segment(&buffer) {
+                            if url.scheme == "file"
+                                && url.path.is_empty()
+                                && is_windows_drive_letter(&buffer)
+                            {
+                                buffer.replace_range(1..2, ":");
+                            }
+                            url.path.push(buffer.clone());
+                        }
+                        buffer.clear();
+                        if c == Some('?') {
+                            url.query = Some(String::new());
+                            state = State::Query;
+                        } else if c == Some('#') {
+                            url.fragment = Some(String::new());
+                            state = State::Fragment;
+                        }
+                    } else if let Some(c) = c {
+                        encode(&mut buffer, c, EncodeSet::Path);
+                    }
+                }
+                State::OpaquePath => match c {
+                    Some('?') => {
+                        url.query = Some(String::new());
+                        state = State::Query;
+                    }
+                    Some('#') => {
+                        url.fragment = Some(String::new());
+                        state = State::Fragment;
+                    }
+                    Some(c) => {
+                        if let Some(path) = url.opaque_path.as_mut() {
+                            encode(path, c, EncodeSet::C0Control);
+                        }
+                    }
+                    None => {}
+                },
+                State::Query => match c {
+                    Some('#') => {
+                        url.fragment = Some(String::new());
+                        state = State::Fragment;
+                    }
+                    Some(c) => {
+                        let set = match url.is_special() {
+                            true => EncodeSet::SpecialQuery,
+                            false => EncodeSet::Query,
+                        };
+                        if let Some(query) = url.query.as_mut() {
+                            encode(query, c, set);
+                        }
+                    }
+                    None => {}
+                },
+                State::Fragment => {
+                    if let (Some(c), Some(fragment)) = (c, url.fragment.as_mut()) {
+                        encode(fragment, c, EncodeSet::Fragment);
+                    }
+                }
+            }
+            if p >= 0 && p as usize >= self.chars.len() {
+                break;
+            }
+            p += 1;
+        }
+        Ok(url)
+    }
+
+    // https://url.spec.whatwg.org/#concept-host-parser
+    fn host(&self, input: &str, is_not_special: bool, start: isize) -> Result<String> {
+        if let Some(rest) = input.strip_prefix('[') {
+            return match rest.strip_suffix(']').and_then(parse_ipv6) {
+                Some(pieces) => Ok(serialize_ipv6(&pieces)),
+                None => Err(self.error(Production::Ipv6Address, start)),
+            };
+        }
+        if is_not_special {
+            if input.chars().any(is_forbidden_host_code_point) {
+                return Err(self.error(Production::Host, start));
+            }
+            let mut host = String::new();
+            for c in input.chars() {
+                encode(&mut host, c, EncodeSet::C0Control);
+            }
+            return Ok(host);
+        }
+
+        let decoded = match percent_decode(input.as_bytes()) {
+            Ok(d) => d.into_owned(),
+            Err(_) => input.as_bytes().to_vec(),
+        };
+        let domain = String::from_utf8_lossy(&decoded);
+        let ascii_domain = match idna::domain_to_ascii(&domain) {
+            Ok(d) if !d.is_empty() => d,
+            _ => return Err(self.error(Production::Host, start)),
+        };
+        if ascii_domain.chars().any(is_forbidden_domain_code_point) {
+            return Err(self.error(Production::Host, start));
+        }
+        if ends_in_a_number(&ascii_domain) {
+            return match parse_ipv4(&ascii_domain) {
+                Some(ip) => Ok(Ipv4Addr::from(ip).to_string()),
+                None => Err(self.error(Production::Host, start)),
+            };
+        }
+        Ok(ascii_domain)
+    }
+}
+
+fn is_special(scheme: &str) -> bool {
+    ["ftp", "file", "http", "https", "ws", "wss"].contains(&scheme)
+}
+
+fn special_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "ftp" => Some(21),
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        _ => None,
+    }
+}
+
+// The end of the authority and its parts, besides "\\" in special URLs.
+fn is_authority_end(c: Option<char>) -> bool {
+    matches!(c, None | Some('/') | Some('?') | Some('#'))
+}
+
+fn is_windows_drive_letter(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 2 && b[0].is_ascii_alphabetic() && (b[1] == b':' || b[1] == b'|')
+}
+
+fn is_normalized_windows_drive_letter(s: &str) -> bool {
+    is_windows_drive_letter(s) && s.as_bytes()[1] == b':'
+}
+
+fn is_single_dot_segment(s: &str) -> bool {
+    s == "." || s.eq_ignore_ascii_case("%2e")
+}
+
+fn is_double_dot_segment(s: &str) -> bool {
+    ["..", ".%2e", "%2e.", "%2e%2e"]
+        .iter()
+        .any(|d| s.eq_ignore_ascii_case(d))
+}
+
+fn is_forbidden_host_code_point(c: char) -> bool {
+    "\0\t\n\r #/:<>?@[\\]^|".contains(c)
+}
+
+fn is_forbidden_domain_code_point(c: char) -> bool {
+    is_forbidden_host_code_point(c) || c <= '\u{1f}' || c == '%' || c == '\u{7f}'
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EncodeSet {
+    C0Control,
+    Fragment,
+    Query,
+    SpecialQuery,
+    Path,
+    Userinfo,
+}
+
+impl EncodeSet {
+    fn contains(self, b: u8) -> bool {
+        let c0_control = b <= 0x1f || b > 0x7e;
+        let fragment = c0_control || b" \"<>`".contains(&b);
+        let query = c0_control || b" \"#<>".contains(&b);
+        let path = query || b"?`{}".contains(&b);
+        match self {
+            EncodeSet::C0Control => c0_control,
+            EncodeSet::Fragment => fragment,
+            EncodeSet::Query => query,
+            EncodeSet::SpecialQuery => query || b == b'\'',
+            EncodeSet::Path => path,
+            EncodeSet::Userinfo => path || b"/:;=@[\\]^|".contains(&b),
+        }
+    }
+}
+
+// UTF-8 percent-encodes a code point.
+fn encode(output: &mut String, c: char, set: EncodeSet) {
+    let mut bytes = [0; 4];
+    for b in c.encode_utf8(&mut bytes).bytes() {
+        match set.contains(b) {
+            true => write!(output, "%{:02X}", b).unwrap(),
+            false => output.push(b as char),
+        }
+    }
+}
+
+// https://url.spec.whatwg.org/#ends-in-a-number-checker
+pub(crate) fn ends_in_a_number(domain: &str) -> bool {
+    let mut parts: Vec<&str> = domain.split('.').collect();
+    if parts[parts.len() - 1].is_empty() {
+        if parts.len() == 1 {
+            return false;
+        }
+        parts.pop();
+    }
+    let last = parts[parts.len() - 1];
+    if !last.is_empty() && last.bytes().all(|b| b.is_ascii_digit()) {
+        return true;
+    }
+    parse_ipv4_number(last).is_some()
+}
+
+// https://url.spec.whatwg.org/#concept-ipv4-parser
+pub(crate) fn parse_ipv4(domain: &str) -> Option<u32> {
+    let mut parts: Vec<&str> = domain.split('.').collect();
+    if parts[parts.len() - 1].is_empty() && parts.len() > 1 {
+        parts.pop();
+    }
+    if parts.len() > 4 {
+        return None;
+    }
+    let mut numbers: Vec<u64> = Vec::with_capacity(4);
+    for part in parts {
+        numbers.push(parse_ipv4_number(part)?);
+    }
+    let last = numbers.pop()?;
+    if numbers.iter().any(|n| *n > 255) || last >= 256u64.pow(4 - numbers.len() as u32) {
+        return None;
+    }
+    let mut ipv4 = last;
+    for (i, n) in numbers.iter().enumerate() {
+        ipv4 += n << (8 * (3 - i));
+    }
+    Some(ipv4 as u32)
+}
+
+// Parses a decimal, "0x" hexadecimal or "0" octal number. Numbers too large for any
+// address saturate instead of failing, as the standard only fails on their size later.
+fn parse_ipv4_number(input: &str) -> Option<u64> {
+    if input.is_empty() {
+        return None;
+    }
+    let (digits, radix) = if input.starts_with("0x") || input.starts_with("0X") {
+        (&input[2..], 16)
+    } else if input.len() >= 2 && input.starts_with('0') {
+        (&input[1..], 8)
+    } else {
+        (input, 10)
+    };
+    let mut n: u64 = 0;
+    for c in digits.chars() {
+        let d = c.to_digit(radix)?;
+        n = n.saturating_mul(radix as u64).saturating_add(d as u64);
+    }
+    Some(n)
+}
+
+// https://url.spec.whatwg.org/#concept-ipv6-parser
+fn parse_ipv6(input: &str) -> Option<[u16; 8]> {
+    let chars: Vec<char> = input.chars().collect();
+    let c = |p: usize| chars.get(p).cloned();
+    let mut address = [0u16; 8];
+    let mut piece_index = 0;
+    let mut compress: Option<usize> = None;
+    let mut p = 0;
+
+    if c(p) == Some(':') {
+        if c(p + 1) != Some(':') {
+            return None;
+        }
+        p += 2;
+        piece_index += 1;
+        compress = Some(piece_index);
+    }
+    while c(p).is_some() {
+        if piece_index == 8 {
+            return None;
+        }
+        if c(p) == Some(':') {
+            if compress.is_some() {
+                return None;
+            }
+            p += 1;
+            piece_index += 1;
+            compress = Some(piece_index);
+            continue;
+        }
+        let mut value: u16 = 0;
+        let mut length = 0;
+        while length < 4 {
+            match c(p).and_then(|c| c.to_digit(16)) {
+                Some(d) => value = value * 0x10 + d as u16,
+                None => break,
+            }
+            p += 1;
+            length += 1;
+        }
+        if c(p) == Some('.') {
+            if length == 0 || piece_index > 6 {
+                return None;
+            }
+            p -= length;
+            let mut numbers_seen = 0;
+            while c(p).is_some() {
+                if numbers_seen > 0 {
+                    match c(p) == Some('.') && numbers_seen < 4 {
+                        true => p += 1,
+                        false => return None,
+                    }
+                }
+                c(p).filter(|c| c.is_ascii_digit())?;
+                let mut ipv4_piece: Option<u16> = None;
+                while let Some(d) = c(p).and_then(|c| c.to_digit(10)) {
+                    ipv4_piece = match ipv4_piece {
+                        None => Some(d as u16),
+                        Some(0) => return None,
+                        Some(n) => Some(n * 10 + d as u16),
+                    };
+                    if ipv4_piece > Some(255) {
+                        return None;
+                    }
+                    p += 1;
+                }
+                address[piece_index] = address[piece_index] * 0x100 + ipv4_piece.unwrap_or(0);
+                numbers_seen += 1;
+                if numbers_seen == 2 || numbers_seen == 4 {
+                    piece_index += 1;
+                }
+            }
+            if numbers_seen != 4 {
+                return None;
+            }
+            break;
+        } else if c(p) == Some(':') {
+            p += 1;
+            c(p)?;
+        } else if c(p).is_some() {
+            return None;
+        }
+        address[piece_index] = value;
+        piece_index += 1;
+    }
+    match compress {
+        Some(compress) => {
+            let mut swaps = piece_index - compress;
+            piece_index = 7;
+            while piece_index != 0 && swaps > 0 {
+                address.swap(piece_index, compress + swaps - 1);
+                piece_index -= 1;
+                swaps -= 1;
+            }
+        }
+        None if piece_index != 8 => return None,
+        None => {}
+    }
+    Some(address)
+}
+
+// https://url.spec.whatwg.org/#concept-ipv6-serializer, which compresses the first
+// longest run of zeros and, unlike `Ipv6Addr`, never writes IPv4 notation.
+fn serialize_ipv6(pieces: &[u16; 8]) -> String {
+    let mut compress: Option<usize> = None;
+    let mut longest = 1;
+    let mut i = 0;
+    while i < 8 {
+        let run = pieces[i..].iter().take_while(|p| **p == 0).count();
+        if run > longest {
+            longest = run;
+            compress = Some(i);
+        }
+        i += run.max(1);
+    }
+    let mut output = String::from("[");
+    let mut ignore_zero = false;
+    for (i, piece) in pieces.iter().enumerate() {
+        if ignore_zero && *piece == 0 {
+            continue;
+        }
+        ignore_zero = false;
+        if compress == Some(i) {
+            output.push_str(if i == 0 { "::" } else { ":" });
+            ignore_zero = true;
+            continue;
+        }
+        write!(output, "{:x}", piece).unwrap();
+        if i != 7 {
+            output.push(':');
+        }
+    }
+    output.push(']');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+    use uri::error::UriErrorKind;
+
+    // The test cases of the web-platform-tests, see the first entry for the revision.
+    const URL_TEST_DATA: &str = include_str!("../../tests/data/urltestdata.json");
+
+    #[test]
+    fn test_urltestdata() {
+        let tests: Vec<Value> = serde_json::from_str(URL_TEST_DATA).unwrap();
+        let mut failures: Vec<String> = Vec::new();
+        for t in tests.iter().filter(|t| t.is_object()) {
+            let input = t["input"].as_str().unwrap();
+            let base = t["base"].as_str();
+            let name = format!("{:?} against {:?}", input, base);
+            let base = match base.map(|b| Uri::parse_whatwg(b, None)).transpose() {
+                Ok(b) => b,
+                Err(e) => {
+                    failures.push(format!("{}: invalid base: {}", name, e));
+                    continue;
+                }
+            };
+            let actual = Uri::parse_whatwg(input, base.as_ref());
+            match (t["failure"].as_bool() == Some(true), actual) {
+                (true, Ok(u)) => failures.push(format!("{}: expected failure, got {}", name, u)),
+                (true, Err(_)) => {}
+                (false, Err(e)) => failures.push(format!("{}: {}", name, e)),
+                (false, Ok(u)) => {
+                    let field = |f: &str| t[f].as_str().unwrap().to_string();
+                    let query = u.query().map(|q| q.to_string()).unwrap_or_default();
+                    let fragment = u.fragment().map(|f| f.to_string()).unwrap_or_default();
+                    let expected = (
+                        field("href"),
+                        field("hostname"),
+                        field("port"),
+                        field("search"),
+                        field("hash"),
+                    );
+                    let actual = (
+                        u.to_string(),
+                        u.host().map(|h| h.to_string()).unwrap_or_default(),
+                        u.port().map(|p| p.to_string()).unwrap_or_default(),
+                        match query.is_empty() {
+                            true => query,
+                            false => format!("?{}", query),
+                        },
+                        match fragment.is_empty() {
+                            true => fragment,
+                            false => format!("#{}", fragment),
+                        },
+                    );
+                    if let Ok(rfc) = Uri::parse(u.to_string().as_bytes()) {
+                        if rfc != u {
+                            failures.push(format!("{}: differs from {:?}", name, rfc));
+                        }
+                    }
+                    if expected != actual {
+                        failures.push(format!(
+                            "{}: expected {:?}, got {:?}",
+                            name, expected, actual
+                        ));
+                    }
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn test_parse_whatwg() -> Result<()> {
+        let u = Uri::parse_whatwg(" HTTP://EXA\tmple.COM:80\\a\\..\\b c?d e#f g ", None)?;
+        assert_eq!("http://example.com/b%20c?d%20e#f%20g", u.to_string());
+
+        let u = Uri::parse_whatwg("http://0x7f.1/", None)?;
+        assert_eq!("http://127.0.0.1/", u.to_string());
+        assert_eq!(
+            Some("127.0.0.1".parse().unwrap()),
+            u.host().unwrap().ip_addr()
+        );
+
+        let base = Uri::parse(b"http://a/b/c/d;p?q")?;
+        let u = Uri::parse_whatwg("../g?x#y", Some(&base))?;
+        assert_eq!("http://a/b/g?x#y", u.to_string());
+
+        let u = Uri::parse_whatwg("http://[0:0::ffff:7f00:1]:8080/", None)?;
+        assert_eq!("http://[::ffff:7f00:1]:8080/", u.to_string());
+        assert_eq!(Some(8080), u.port());
+
+        let u = Uri::parse_whatwg("sc://x{y}/p^q|r", None)?;
+        assert_eq!("sc://x{y}/p^q|r", u.to_string());
+        assert!(Uri::parse(u.to_string().as_bytes()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_whatwg_errors() {
+        let e = Uri::parse_whatwg("http://a:b/", None).unwrap_err();
+        assert_eq!(Production::Port, e.production());
+        assert_eq!(9, e.offset());
+        assert_eq!(
+            "Invalid port at byte 9.\nhttp://a:b/\n         ^",
+            e.to_string()
+        );
+
+        let e = Uri::parse_whatwg("http://1.2.3.256/", None).unwrap_err();
+        assert_eq!(Production::Host, e.production());
+        assert_eq!(7, e.offset());
+
+        let e = Uri::parse_whatwg("http://[::1::]/", None).unwrap_err();
+        assert_eq!(Production::Ipv6Address, e.production());
+
+        let e = Uri::parse_whatwg("//a/b", None).unwrap_err();
+        assert_eq!(Production::Scheme, e.production());
+        assert_eq!(UriErrorKind::Invalid, e.kind());
+    }
+}